@@ -0,0 +1,677 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    memory::{Endianness, MemoryMap},
+    record::{binary::BinaryImportError, file::IHexFile},
+};
+
+/// The reflected CRC-32 polynomial of IEEE 802.3, as used by zlib, PNG and Ethernet.
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// The CRC-16/CCITT polynomial, processed most significant bit first.
+const CRC16_CCITT_POLYNOMIAL: u16 = 0x1021;
+/// The reflected CRC-16/MODBUS (IBM) polynomial.
+const CRC16_MODBUS_POLYNOMIAL: u16 = 0xA001;
+/// The CRC-32 polynomial, processed most significant bit first as in CRC-32/MPEG-2.
+const CRC32_MPEG2_POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+const fn crc16_msb_table(polynomial: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ polynomial
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+const fn crc16_reflected_table(polynomial: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u16;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ polynomial
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+const fn crc32_msb_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ polynomial
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+const CRC16_CCITT_TABLE: [u16; 256] = crc16_msb_table(CRC16_CCITT_POLYNOMIAL);
+const CRC16_MODBUS_TABLE: [u16; 256] = crc16_reflected_table(CRC16_MODBUS_POLYNOMIAL);
+const CRC32_MPEG2_TABLE: [u32; 256] = crc32_msb_table(CRC32_MPEG2_POLYNOMIAL);
+
+/// The algorithms that can be used to calculate a checksum over an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumAlgorithm {
+    /// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, not reflected.
+    Crc16Ccitt,
+    /// CRC-16/MODBUS: polynomial 0x8005, initial value 0xFFFF, reflected.
+    Crc16Modbus,
+    /// CRC-32 (IEEE 802.3), as used by zlib, PNG and Ethernet.
+    #[default]
+    Crc32,
+    /// CRC-32/MPEG-2: the CRC-32 polynomial, not reflected and without a final XOR.
+    /// This is what the STM32 CRC peripheral calculates in its default configuration,
+    /// when it is fed the image a byte at a time.
+    Crc32Mpeg2,
+    /// The sum of all bytes, truncated to 8 bits.
+    Sum8,
+    /// The sum of all bytes, truncated to 16 bits.
+    Sum16,
+    /// The sum of all bytes, truncated to 32 bits.
+    Sum32,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 8] = [
+        ChecksumAlgorithm::Crc16Ccitt,
+        ChecksumAlgorithm::Crc16Modbus,
+        ChecksumAlgorithm::Crc32,
+        ChecksumAlgorithm::Crc32Mpeg2,
+        ChecksumAlgorithm::Sum8,
+        ChecksumAlgorithm::Sum16,
+        ChecksumAlgorithm::Sum32,
+        ChecksumAlgorithm::Sha256,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc16Ccitt => "CRC-16/CCITT",
+            ChecksumAlgorithm::Crc16Modbus => "CRC-16/MODBUS",
+            ChecksumAlgorithm::Crc32 => "CRC-32",
+            ChecksumAlgorithm::Crc32Mpeg2 => "CRC-32/MPEG-2",
+            ChecksumAlgorithm::Sum8 => "Sum-8",
+            ChecksumAlgorithm::Sum16 => "Sum-16",
+            ChecksumAlgorithm::Sum32 => "Sum-32",
+            ChecksumAlgorithm::Sha256 => "SHA-256",
+        }
+    }
+
+    /// The checksum of `data`.
+    pub fn compute(&self, data: &[u8]) -> ChecksumValue {
+        let mut checksum = Checksum::new(*self);
+        checksum.update(data);
+        checksum.finish()
+    }
+}
+
+/// The result of a checksum calculation, with the width of its algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    Sha256([u8; 32]),
+}
+
+impl ChecksumValue {
+    /// The bytes of the value in the given byte order. A SHA-256 digest is always
+    /// returned in its usual order, regardless of `endianness`.
+    pub fn to_bytes(&self, endianness: Endianness) -> Vec<u8> {
+        match (self, endianness) {
+            (ChecksumValue::U8(value), _) => vec![*value],
+            (ChecksumValue::U16(value), Endianness::Little) => value.to_le_bytes().to_vec(),
+            (ChecksumValue::U16(value), Endianness::Big) => value.to_be_bytes().to_vec(),
+            (ChecksumValue::U32(value), Endianness::Little) => value.to_le_bytes().to_vec(),
+            (ChecksumValue::U32(value), Endianness::Big) => value.to_be_bytes().to_vec(),
+            (ChecksumValue::Sha256(digest), _) => digest.to_vec(),
+        }
+    }
+}
+
+impl Display for ChecksumValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumValue::U8(value) => write!(f, "0x{:02X}", value),
+            ChecksumValue::U16(value) => write!(f, "0x{:04X}", value),
+            ChecksumValue::U32(value) => write!(f, "0x{:08X}", value),
+            ChecksumValue::Sha256(digest) => write!(f, "{}", hex::encode(digest)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ChecksumState {
+    Crc16(u16),
+    Crc32(u32),
+    Crc32Msb(u32),
+    Sum(u32),
+    Sha256(Box<Sha256>),
+}
+
+/// An incremental checksum calculation with any of the [`ChecksumAlgorithm`]s.
+#[derive(Debug, Clone)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+    state: ChecksumState,
+}
+
+impl Checksum {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        let state = match algorithm {
+            ChecksumAlgorithm::Crc16Ccitt | ChecksumAlgorithm::Crc16Modbus => {
+                ChecksumState::Crc16(0xFFFF)
+            }
+            ChecksumAlgorithm::Crc32 => ChecksumState::Crc32(0xFFFF_FFFF),
+            ChecksumAlgorithm::Crc32Mpeg2 => ChecksumState::Crc32Msb(0xFFFF_FFFF),
+            ChecksumAlgorithm::Sum8 | ChecksumAlgorithm::Sum16 | ChecksumAlgorithm::Sum32 => {
+                ChecksumState::Sum(0)
+            }
+            ChecksumAlgorithm::Sha256 => ChecksumState::Sha256(Box::new(Sha256::new())),
+        };
+
+        Checksum { algorithm, state }
+    }
+
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match (&mut self.state, self.algorithm) {
+            (ChecksumState::Crc16(crc), ChecksumAlgorithm::Crc16Modbus) => {
+                for byte in data {
+                    let index = (*crc ^ *byte as u16) & 0xFF;
+                    *crc = (*crc >> 8) ^ CRC16_MODBUS_TABLE[index as usize];
+                }
+            }
+            (ChecksumState::Crc16(crc), _) => {
+                for byte in data {
+                    let index = ((*crc >> 8) ^ *byte as u16) & 0xFF;
+                    *crc = (*crc << 8) ^ CRC16_CCITT_TABLE[index as usize];
+                }
+            }
+            (ChecksumState::Crc32(crc), _) => {
+                for byte in data {
+                    let index = (*crc ^ *byte as u32) & 0xFF;
+                    *crc = (*crc >> 8) ^ CRC32_TABLE[index as usize];
+                }
+            }
+            (ChecksumState::Crc32Msb(crc), _) => {
+                for byte in data {
+                    let index = ((*crc >> 24) ^ *byte as u32) & 0xFF;
+                    *crc = (*crc << 8) ^ CRC32_MPEG2_TABLE[index as usize];
+                }
+            }
+            (ChecksumState::Sum(sum), _) => {
+                for byte in data {
+                    *sum = sum.wrapping_add(*byte as u32);
+                }
+            }
+            (ChecksumState::Sha256(sha), _) => sha.update(data),
+        }
+    }
+
+    /// The checksum of all data passed to [`Checksum::update`] so far.
+    pub fn finish(&self) -> ChecksumValue {
+        match (&self.state, self.algorithm) {
+            (ChecksumState::Crc16(crc), _) => ChecksumValue::U16(*crc),
+            (ChecksumState::Crc32(crc), _) => ChecksumValue::U32(!*crc),
+            (ChecksumState::Crc32Msb(crc), _) => ChecksumValue::U32(*crc),
+            (ChecksumState::Sum(sum), ChecksumAlgorithm::Sum8) => ChecksumValue::U8(*sum as u8),
+            (ChecksumState::Sum(sum), ChecksumAlgorithm::Sum16) => ChecksumValue::U16(*sum as u16),
+            (ChecksumState::Sum(sum), _) => ChecksumValue::U32(*sum),
+            (ChecksumState::Sha256(sha), _) => {
+                ChecksumValue::Sha256(sha.as_ref().clone().finalize().into())
+            }
+        }
+    }
+}
+
+/// The size of the buffer of fill bytes used for unmapped addresses
+const FILL_CHUNK_SIZE: usize = 4096;
+
+/// Adds `len` copies of `fill` to the checksum
+fn update_fill(checksum: &mut Checksum, fill: u8, len: u64) {
+    let chunk = [fill; FILL_CHUNK_SIZE];
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let len = remaining.min(FILL_CHUNK_SIZE as u64);
+        checksum.update(&chunk[..len as usize]);
+        remaining -= len;
+    }
+}
+
+/// The checksum of the bytes of `memory` in `range`, with `fill` for unmapped addresses.
+/// Without a range, the checksum is calculated from the lowest to the highest mapped address.
+pub fn memory_checksum(
+    memory: &MemoryMap,
+    algorithm: ChecksumAlgorithm,
+    range: Option<RangeInclusive<u32>>,
+    fill: u8,
+) -> ChecksumValue {
+    let mut checksum = Checksum::new(algorithm);
+
+    let (start, end) = match range {
+        Some(range) if range.is_empty() => return checksum.finish(),
+        Some(range) => (*range.start() as u64, *range.end() as u64 + 1),
+        None => match (memory.min_address(), memory.max_address()) {
+            (Some(min), Some(max)) => (min as u64, max as u64 + 1),
+            _ => return checksum.finish(),
+        },
+    };
+
+    let mut address = start;
+
+    for segment in memory.segments() {
+        if segment.end() <= address {
+            continue;
+        }
+
+        if segment.start as u64 >= end {
+            break;
+        }
+
+        let data_start = address.max(segment.start as u64);
+        let data_end = end.min(segment.end());
+
+        update_fill(&mut checksum, fill, data_start - address);
+        checksum.update(
+            &segment.data[(data_start - segment.start as u64) as usize
+                ..(data_end - segment.start as u64) as usize],
+        );
+
+        address = data_end;
+    }
+
+    update_fill(&mut checksum, fill, end - address);
+
+    checksum.finish()
+}
+
+/// Options for [`insert_checksum`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumInsertOptions {
+    pub algorithm: ChecksumAlgorithm,
+    /// The range the checksum is calculated over. Defaults to the lowest to the highest
+    /// address in the file.
+    pub range: Option<RangeInclusive<u32>>,
+    /// The byte used for unmapped addresses in the range.
+    pub fill: u8,
+    /// The byte order the checksum is stored in. SHA-256 digests are always stored as is.
+    pub endianness: Endianness,
+    /// The address the checksum is stored at.
+    pub address: u32,
+    /// The maximum amount of data bytes per data record, if a record has to be created.
+    pub record_size: u8,
+}
+
+/// Calculates a checksum over `file`, and generates a copy of the file with the checksum
+/// stored at `options.address`. If the address is unmapped, a data record is created for it.
+/// Returns the new file along with the checksum.
+pub fn insert_checksum(
+    file: &IHexFile,
+    options: &ChecksumInsertOptions,
+) -> Result<(IHexFile, ChecksumValue), ChecksumInsertError> {
+    let memory = file.data_bytes();
+    let range = options
+        .range
+        .clone()
+        .or_else(|| Some(memory.min_address()?..=memory.max_address()?));
+
+    let value = memory_checksum(&memory, options.algorithm, range.clone(), options.fill);
+    let bytes = value.to_bytes(options.endianness);
+
+    let target = options.address as u64..options.address as u64 + bytes.len() as u64;
+    let overlaps = range.is_some_and(|range| {
+        target.start <= *range.end() as u64 && (*range.start() as u64) < target.end
+    });
+
+    if overlaps {
+        return Err(ChecksumInsertError::TargetInRange {
+            address: options.address,
+        });
+    }
+
+    let patched = file.with_bytes(options.address, &bytes, options.record_size)?;
+
+    Ok((patched, value))
+}
+
+#[derive(Debug)]
+pub enum ChecksumInsertError {
+    /// The checksum would be stored inside the range it is calculated over.
+    TargetInRange {
+        address: u32,
+    },
+    Import(BinaryImportError),
+}
+
+impl Display for ChecksumInsertError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumInsertError::TargetInRange { address } => write!(
+                f,
+                "The checksum at 0x{:08X} would overlap the range it is calculated over",
+                address
+            ),
+            ChecksumInsertError::Import(e) => write!(f, "Could not store the checksum: {}", e),
+        }
+    }
+}
+
+impl Error for ChecksumInsertError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ChecksumInsertError::Import(e) => Some(e),
+            ChecksumInsertError::TargetInRange { .. } => None,
+        }
+    }
+}
+
+impl From<BinaryImportError> for ChecksumInsertError {
+    fn from(e: BinaryImportError) -> Self {
+        ChecksumInsertError::Import(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::binary::BinaryImportOptions;
+
+    const CHECK_INPUT: &[u8] = b"123456789";
+
+    fn sha256_hex(data: &[u8]) -> String {
+        ChecksumAlgorithm::Sha256.compute(data).to_string()
+    }
+
+    #[test]
+    fn check_values() {
+        let expected = [
+            (ChecksumAlgorithm::Crc16Ccitt, ChecksumValue::U16(0x29B1)),
+            (ChecksumAlgorithm::Crc16Modbus, ChecksumValue::U16(0x4B37)),
+            (ChecksumAlgorithm::Crc32, ChecksumValue::U32(0xCBF4_3926)),
+            (
+                ChecksumAlgorithm::Crc32Mpeg2,
+                ChecksumValue::U32(0x0376_E6E7),
+            ),
+            (ChecksumAlgorithm::Sum8, ChecksumValue::U8(0xDD)),
+            (ChecksumAlgorithm::Sum16, ChecksumValue::U16(0x01DD)),
+            (ChecksumAlgorithm::Sum32, ChecksumValue::U32(0x0000_01DD)),
+        ];
+
+        for (algorithm, value) in expected {
+            assert_eq!(
+                algorithm.compute(CHECK_INPUT),
+                value,
+                "{}",
+                algorithm.name()
+            );
+        }
+    }
+
+    #[test]
+    fn sha256_digests() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(CHECK_INPUT),
+            "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+
+        // 56 bytes, so the length no longer fits in the first padded block
+        assert_eq!(
+            sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256_hex(&[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        for algorithm in ChecksumAlgorithm::ALL {
+            let mut checksum = Checksum::new(algorithm);
+
+            for chunk in data.chunks(63) {
+                checksum.update(chunk);
+            }
+
+            assert_eq!(
+                checksum.finish(),
+                algorithm.compute(&data),
+                "{}",
+                algorithm.name()
+            );
+        }
+    }
+
+    #[test]
+    fn memory_checksum_fills_gaps() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x10, &[0x01, 0x02]);
+        memory.insert(0x14, &[0x03]);
+
+        let flat = [0x01, 0x02, 0xFF, 0xFF, 0x03];
+
+        for algorithm in ChecksumAlgorithm::ALL {
+            assert_eq!(
+                memory_checksum(&memory, algorithm, None, 0xFF),
+                algorithm.compute(&flat),
+                "{}",
+                algorithm.name()
+            );
+        }
+
+        assert_eq!(
+            memory_checksum(&memory, ChecksumAlgorithm::Crc32, Some(0x0E..=0x11), 0x00),
+            ChecksumAlgorithm::Crc32.compute(&[0x00, 0x00, 0x01, 0x02])
+        );
+    }
+
+    #[test]
+    fn value_bytes() {
+        assert_eq!(
+            ChecksumValue::U32(0x1122_3344).to_bytes(Endianness::Little),
+            [0x44, 0x33, 0x22, 0x11]
+        );
+        assert_eq!(
+            ChecksumValue::U16(0x1122).to_bytes(Endianness::Big),
+            [0x11, 0x22]
+        );
+    }
+
+    #[test]
+    fn memory_checksum_ranges() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x10, &[0x01, 0x02]);
+        memory.insert(0xFFFF_FFFF, &[0x03]);
+
+        let check = |range, expected: &[u8]| {
+            assert_eq!(
+                memory_checksum(&memory, ChecksumAlgorithm::Sum32, Some(range), 0xFF),
+                ChecksumAlgorithm::Sum32.compute(expected),
+            );
+        };
+
+        check(0x0F..=0x12, &[0xFF, 0x01, 0x02, 0xFF]);
+        check(0x11..=0x11, &[0x02]);
+        check(0x100..=0x103, &[0xFF; 4]);
+        check(0xFFFF_FFFE..=0xFFFF_FFFF, &[0xFF, 0x03]);
+
+        // An empty range, or an empty image without a range, checksums no data
+        check(RangeInclusive::new(0x20, 0x1F), &[]);
+        assert_eq!(
+            memory_checksum(&MemoryMap::new(), ChecksumAlgorithm::Crc32, None, 0xFF),
+            ChecksumAlgorithm::Crc32.compute(&[])
+        );
+    }
+
+    fn file(address: u32, data: &[u8]) -> IHexFile {
+        let mut memory = MemoryMap::new();
+        memory.insert(address, data);
+
+        IHexFile::from_memory_map(&memory, &BinaryImportOptions::default()).unwrap()
+    }
+
+    fn insert_options(algorithm: ChecksumAlgorithm, address: u32) -> ChecksumInsertOptions {
+        ChecksumInsertOptions {
+            algorithm,
+            range: None,
+            fill: 0xFF,
+            endianness: Endianness::Big,
+            address,
+            record_size: 16,
+        }
+    }
+
+    #[test]
+    fn insert_checksum_value() {
+        let file = file(0x100, &[1, 2, 3, 4]);
+
+        let options = insert_options(ChecksumAlgorithm::Crc32, 0x200);
+        let (patched, value) = insert_checksum(&file, &options).unwrap();
+        let memory = patched.data_bytes();
+
+        assert_eq!(value, ChecksumAlgorithm::Crc32.compute(&[1, 2, 3, 4]));
+        assert_eq!(memory.read(0x100, 4, 0), [1, 2, 3, 4]);
+        assert_eq!(memory.read(0x200, 4, 0), value.to_bytes(Endianness::Big));
+        assert_eq!(memory.len(), 8);
+
+        // Right after the range, in little endian
+        let options = ChecksumInsertOptions {
+            endianness: Endianness::Little,
+            ..insert_options(ChecksumAlgorithm::Sum16, 0x104)
+        };
+        let (patched, _) = insert_checksum(&file, &options).unwrap();
+
+        assert_eq!(patched.data_bytes().read(0x104, 2, 0), [0x0A, 0x00]);
+
+        // The range can include unmapped addresses, and SHA-256 digests take 32 bytes
+        let options = ChecksumInsertOptions {
+            range: Some(0x100..=0x1FF),
+            ..insert_options(ChecksumAlgorithm::Sha256, 0x200)
+        };
+        let (patched, value) = insert_checksum(&file, &options).unwrap();
+
+        let mut expected = vec![0xFF; 0x100];
+        expected[..4].copy_from_slice(&[1, 2, 3, 4]);
+
+        assert_eq!(value, ChecksumAlgorithm::Sha256.compute(&expected));
+        assert_eq!(patched.data_bytes().len(), 36);
+    }
+
+    #[test]
+    fn insert_checksum_target() {
+        let file = file(0x100, &[1, 2, 3, 4]);
+
+        // The last byte of the checksum would be the first byte of the range
+        let options = ChecksumInsertOptions {
+            range: Some(0x100..=0x103),
+            ..insert_options(ChecksumAlgorithm::Crc16Ccitt, 0xFF)
+        };
+        assert!(matches!(
+            insert_checksum(&file, &options),
+            Err(ChecksumInsertError::TargetInRange { address: 0xFF })
+        ));
+
+        let options = insert_options(ChecksumAlgorithm::Crc16Ccitt, 0x103);
+        assert!(matches!(
+            insert_checksum(&file, &options),
+            Err(ChecksumInsertError::TargetInRange { address: 0x103 })
+        ));
+
+        let options = insert_options(ChecksumAlgorithm::Crc16Ccitt, 0xFE);
+        assert!(insert_checksum(&file, &options).is_ok());
+
+        let options = insert_options(ChecksumAlgorithm::Crc32, 0xFFFF_FFFE);
+        assert!(matches!(
+            insert_checksum(&file, &options),
+            Err(ChecksumInsertError::Import(
+                BinaryImportError::AddressOutOfRange
+            ))
+        ));
+    }
+}
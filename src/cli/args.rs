@@ -1,38 +1,653 @@
 use std::path::PathBuf;
 
-use clap::{Args, Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use cpr_ihex::{
+    checksum::ChecksumAlgorithm,
+    formats::{srec::SRecordWriteOptions, titxt::TiTxtWriteOptions, FileFormat},
+    memory::{parse_address, parse_number, Endianness},
+    merge::{ConflictPolicy, MergeOptions},
+    record::{
+        binary::{AddressingMode, BinaryImportOptions},
+        file::{IHexFileType, IHexWriteOptions, LineEnding, SegmentStartAddr, StartAddr},
+        raw::HexCase,
+        validate::ValidationOptions,
+    },
+    search::TextEncoding,
+};
 
 #[derive(Parser, Debug)]
-#[command(author, about, version)]
+#[command(author, about, version, args_conflicts_with_subcommands = true)]
 pub(crate) struct CLIArgs {
-    /// The hex file to parse.
+    #[command(subcommand)]
+    pub subcommand: Option<CLISubcommand>,
+
+    /// The file to open in the GUI.
     #[arg()]
     pub file: Option<PathBuf>,
 
+    /// Skip invalid records instead of aborting when reading the file.
+    #[arg(short, long, requires = "file")]
+    pub lenient: bool,
+
     #[command(flatten)]
-    pub commands: CLICommands,
+    pub binary_options: CLIBinaryOptions,
 
     /// The verbosity of the logger
     #[cfg(not(debug_assertions))]
-    #[arg(value_enum, short, long, default_value_t = LogLevel::Warn)]
+    #[arg(value_enum, short, long, global = true, default_value_t = LogLevel::Warn)]
     pub verbosity: LogLevel,
 
     /// The verbosity of the logger
     #[cfg(debug_assertions)]
-    #[arg(value_enum, short, long, default_value_t = LogLevel::Info)]
+    #[arg(value_enum, short, long, global = true, default_value_t = LogLevel::Info)]
     pub verbosity: LogLevel,
 }
 
+#[derive(Subcommand, Debug)]
+pub(crate) enum CLISubcommand {
+    /// Print a summary of the file: its type, record counts, start address, data regions and checksum.
+    Info(CLIInfoArgs),
+
+    /// Print a prettified hexdump of the data in the file, per contiguous region.
+    Dump(CLIDumpArgs),
+
+    /// Convert the file to another format, or re-serialize it as Intel HEX.
+    Convert(CLIConvertArgs),
+
+    /// Check the structure of the file. Exits with an error if any problems are found.
+    Validate(CLIValidateArgs),
+
+    /// Search the data of the file for a byte pattern or string, and print the address of every match.
+    /// Exits with an error if nothing was found.
+    Find(CLIFindArgs),
+
+    /// Merge the data of several files into a single Intel HEX file, e.g. a bootloader, an application and a
+    /// configuration page. Exits with an error if the files overlap, unless a conflict policy allows it.
+    Merge(CLIMergeArgs),
+
+    /// Compare the data of two files by address, and print the changed ranges, the ranges only present in one file
+    /// and a differing start address. Exits with an error if the files differ.
+    Diff(CLIDiffArgs),
+
+    /// Calculate CRCs, sums and a SHA-256 hash over the data of the file or over an address range, with unmapped
+    /// addresses filled with the fill byte.
+    Checksum(CLIChecksumArgs),
+
+    /// Calculate a checksum over the data of the file or over an address range, and write the file as Intel HEX
+    /// with the checksum stored at the given address. A data record is created if the address is unmapped.
+    InsertChecksum(CLIInsertChecksumArgs),
+}
+
+/// The file a subcommand reads
+#[derive(Args, Debug)]
+pub(crate) struct CLIInput {
+    /// The file to read.
+    pub file: PathBuf,
+
+    /// The format of the file. Guessed from the file extension and contents if not given, falling
+    /// back to Intel HEX.
+    #[arg(value_enum, long, conflicts_with = "binary_base")]
+    pub from: Option<CLIInputFormat>,
+
+    /// Skip invalid records instead of aborting when reading the file.
+    #[arg(short, long)]
+    pub lenient: bool,
+
+    #[command(flatten)]
+    pub binary_options: CLIBinaryOptions,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIInputFormat {
+    /// Intel HEX
+    Ihex,
+    /// Motorola S-record
+    Srec,
+    /// TI-TXT
+    Titxt,
+    /// The loadable segments of an ELF executable, at their physical addresses
+    Elf,
+}
+
+impl From<CLIInputFormat> for FileFormat {
+    fn from(format: CLIInputFormat) -> Self {
+        match format {
+            CLIInputFormat::Ihex => FileFormat::IHex,
+            CLIInputFormat::Srec => FileFormat::SRecord,
+            CLIInputFormat::Titxt => FileFormat::TiTxt,
+            CLIInputFormat::Elf => FileFormat::Elf,
+        }
+    }
+}
+
+/// Where a subcommand writes its output
+#[derive(Args, Debug)]
+pub(crate) struct CLIOutput {
+    /// Write the output to this file instead of to stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIInfoArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The format of the summary.
+    #[arg(value_enum, short, long, default_value_t = CLIReportFormat::Text)]
+    pub format: CLIReportFormat,
+}
+
+/// The format of the reports of the `info` and `diff` subcommands
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIDumpArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIConvertArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The format to write.
+    #[arg(value_enum, short, long, default_value_t = CLIOutputFormat::Ihex)]
+    pub to: CLIOutputFormat,
+
+    /// The byte used to fill gaps between regions in binary and memory initialization output.
+    #[arg(long, value_parser = parse_u8, default_value = "0xFF")]
+    pub fill: u8,
+
+    #[command(flatten)]
+    pub write_options: CLIWriteOptions,
+
+    #[command(flatten)]
+    pub source_options: CLISourceOptions,
+
+    #[command(flatten)]
+    pub meminit_options: CLIMemInitOptions,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIOutputFormat {
+    /// Intel HEX
+    Ihex,
+    /// Motorola S-record, using S1, S2 or S3 data records depending on the highest address
+    Srec,
+    /// TI-TXT, with one section per contiguous region
+    Titxt,
+    /// A raw binary image, starting at the lowest address in the file
+    Binary,
+    /// A C header with an array and address and length macros per contiguous region
+    C,
+    /// Rust source with a static array and an address constant per contiguous region
+    Rust,
+    /// One hex word per line, for the Verilog $readmemh task
+    Readmemh,
+    /// A Xilinx coefficient file
+    Coe,
+    /// An Altera/Intel memory initialization file
+    Mif,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIMemInitOptions {
+    /// The width of a memory word in bytes.
+    #[arg(help_heading = "Memory initialization output", long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..))]
+    pub word_width: u8,
+
+    /// The order of the bytes within a memory word.
+    #[arg(help_heading = "Memory initialization output", value_enum, long, default_value_t = CLIEndianness::Little)]
+    pub endianness: CLIEndianness,
+
+    /// The address of the first memory word. Defaults to the lowest address in the file.
+    #[arg(help_heading = "Memory initialization output", long, value_parser = parse_addr)]
+    pub base_address: Option<u32>,
+
+    /// The amount of words in the memory. The data is padded with the fill byte up to this depth.
+    #[arg(help_heading = "Memory initialization output", long)]
+    pub depth: Option<u64>,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIEndianness {
+    Little,
+    Big,
+}
+
+impl From<CLIEndianness> for Endianness {
+    fn from(endianness: CLIEndianness) -> Self {
+        match endianness {
+            CLIEndianness::Little => Endianness::Little,
+            CLIEndianness::Big => Endianness::Big,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLISourceOptions {
+    /// The name of the generated array. The index of the region is appended when there are multiple regions.
+    #[arg(help_heading = "Source output", long, default_value = "image")]
+    pub name: String,
+
+    /// The amount of bytes per line of the generated array.
+    #[arg(help_heading = "Source output", long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(1..))]
+    pub columns: u8,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIValidateArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    #[command(flatten)]
+    pub options: CLIValidateOptions,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIFindArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The hex bytes to search for, where ?? matches any byte. For example "DE AD ?? EF".
+    #[arg(required_unless_present = "string", conflicts_with = "string")]
+    pub pattern: Option<String>,
+
+    /// Search for a string instead of a byte pattern.
+    #[arg(short, long)]
+    pub string: Option<String>,
+
+    /// The encoding of the search string.
+    #[arg(value_enum, short, long, default_value_t = CLITextEncoding::Ascii)]
+    pub encoding: CLITextEncoding,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIMergeArgs {
+    /// The files to merge, in order. The format of each file is guessed from its extension and contents.
+    #[arg(required = true, num_args = 1..)]
+    pub files: Vec<PathBuf>,
+
+    /// Skip invalid records instead of aborting when reading the files.
+    #[arg(short, long)]
+    pub lenient: bool,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// How addresses that are present in more than one file are handled.
+    #[arg(value_enum, short, long, default_value_t = CLIConflictPolicy::Fail)]
+    pub conflicts: CLIConflictPolicy,
+
+    /// The start address of the merged file. Either a linear address, or a CS:IP pair. Defaults to the first start
+    /// address found in the files.
+    #[arg(long, value_parser = parse_start_addr)]
+    pub start_address: Option<StartAddr>,
+
+    /// The maximum amount of data bytes per data record.
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(1..))]
+    pub record_size: u8,
+
+    /// The kind of extended address records to generate.
+    #[arg(value_enum, long, default_value_t = CLIAddressingMode::Linear)]
+    pub addressing: CLIAddressingMode,
+
+    #[command(flatten)]
+    pub write_options: CLIWriteOptions,
+}
+
+impl From<&CLIMergeArgs> for MergeOptions {
+    fn from(args: &CLIMergeArgs) -> Self {
+        MergeOptions {
+            conflicts: args.conflicts.clone().into(),
+            start_address: args.start_address,
+            record_size: args.record_size,
+            addressing: args.addressing.clone().into(),
+        }
+    }
+}
+
 #[derive(Args, Debug)]
-#[group(id = "commands", multiple = false, requires = "file")]
-pub(crate) struct CLICommands {
-    /// If set, the program will output a prettified hexdump of the file instead of opening the GUI.
-    #[arg(short = 'x', long)]
-    pub hexdump: bool,
+pub(crate) struct CLIDiffArgs {
+    /// The original file. The format is guessed from the file extension and contents.
+    pub a: PathBuf,
+
+    /// The file to compare it with. The format is guessed from the file extension and contents.
+    pub b: PathBuf,
 
-    /// If set, the program will output a binary dump of the file instead of opening the GUI.
+    /// Skip invalid records instead of aborting when reading the files.
     #[arg(short, long)]
-    pub bindump: bool,
+    pub lenient: bool,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The format of the differences. Text output resembles a unified diff.
+    #[arg(value_enum, short, long, default_value_t = CLIReportFormat::Text)]
+    pub format: CLIReportFormat,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIChecksumArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The algorithms to calculate, separated by commas. Defaults to all algorithms.
+    #[arg(value_enum, short, long, value_delimiter = ',')]
+    pub algorithm: Vec<CLIChecksumAlgorithm>,
+
+    /// The first address of the range. Defaults to the lowest address in the file.
+    #[arg(long, value_parser = parse_addr)]
+    pub start: Option<u32>,
+
+    /// The last address of the range, inclusive. Defaults to the highest address in the file.
+    #[arg(long, value_parser = parse_addr)]
+    pub end: Option<u32>,
+
+    /// The byte used for unmapped addresses in the range.
+    #[arg(long, value_parser = parse_u8, default_value = "0xFF")]
+    pub fill: u8,
+
+    /// The format of the checksums.
+    #[arg(value_enum, short, long, default_value_t = CLIReportFormat::Text)]
+    pub format: CLIReportFormat,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIInsertChecksumArgs {
+    #[command(flatten)]
+    pub input: CLIInput,
+
+    #[command(flatten)]
+    pub output: CLIOutput,
+
+    /// The algorithm to calculate.
+    #[arg(value_enum, short, long)]
+    pub algorithm: CLIChecksumAlgorithm,
+
+    /// The address the checksum is stored at. It may not lie inside the range.
+    #[arg(long, value_parser = parse_addr)]
+    pub address: u32,
+
+    /// The byte order the checksum is stored in. SHA-256 digests are always stored as is.
+    #[arg(value_enum, long, default_value_t = CLIEndianness::Little)]
+    pub endianness: CLIEndianness,
+
+    /// The first address of the range. Defaults to the lowest address in the file.
+    #[arg(long, value_parser = parse_addr)]
+    pub start: Option<u32>,
+
+    /// The last address of the range, inclusive. Defaults to the highest address in the file.
+    #[arg(long, value_parser = parse_addr)]
+    pub end: Option<u32>,
+
+    /// The byte used for unmapped addresses in the range.
+    #[arg(long, value_parser = parse_u8, default_value = "0xFF")]
+    pub fill: u8,
+
+    #[command(flatten)]
+    pub write_options: CLIWriteOptions,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIChecksumAlgorithm {
+    /// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF)
+    Crc16Ccitt,
+    /// CRC-16/MODBUS (polynomial 0x8005 reflected, initial value 0xFFFF)
+    Crc16Modbus,
+    /// CRC-32 (IEEE 802.3), as used by zlib
+    Crc32,
+    /// CRC-32/MPEG-2, as calculated by the STM32 CRC peripheral when fed a byte at a time
+    Crc32Mpeg2,
+    /// The sum of all bytes, truncated to 8 bits
+    Sum8,
+    /// The sum of all bytes, truncated to 16 bits
+    Sum16,
+    /// The sum of all bytes, truncated to 32 bits
+    Sum32,
+    /// SHA-256
+    Sha256,
+}
+
+impl From<CLIChecksumAlgorithm> for ChecksumAlgorithm {
+    fn from(algorithm: CLIChecksumAlgorithm) -> Self {
+        match algorithm {
+            CLIChecksumAlgorithm::Crc16Ccitt => ChecksumAlgorithm::Crc16Ccitt,
+            CLIChecksumAlgorithm::Crc16Modbus => ChecksumAlgorithm::Crc16Modbus,
+            CLIChecksumAlgorithm::Crc32 => ChecksumAlgorithm::Crc32,
+            CLIChecksumAlgorithm::Crc32Mpeg2 => ChecksumAlgorithm::Crc32Mpeg2,
+            CLIChecksumAlgorithm::Sum8 => ChecksumAlgorithm::Sum8,
+            CLIChecksumAlgorithm::Sum16 => ChecksumAlgorithm::Sum16,
+            CLIChecksumAlgorithm::Sum32 => ChecksumAlgorithm::Sum32,
+            CLIChecksumAlgorithm::Sha256 => ChecksumAlgorithm::Sha256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIConflictPolicy {
+    /// Fail if any address is present in more than one file
+    Fail,
+    /// Keep the bytes of the earliest file
+    PreferFirst,
+    /// Keep the bytes of the latest file
+    PreferLast,
+    /// Only allow overlap where the files contain the same bytes
+    AllowIdentical,
+}
+
+impl From<CLIConflictPolicy> for ConflictPolicy {
+    fn from(policy: CLIConflictPolicy) -> Self {
+        match policy {
+            CLIConflictPolicy::Fail => ConflictPolicy::Fail,
+            CLIConflictPolicy::PreferFirst => ConflictPolicy::PreferFirst,
+            CLIConflictPolicy::PreferLast => ConflictPolicy::PreferLast,
+            CLIConflictPolicy::AllowIdentical => ConflictPolicy::AllowIdentical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLITextEncoding {
+    Ascii,
+    Utf8,
+    Utf16le,
+    Utf16be,
+}
+
+impl From<CLITextEncoding> for TextEncoding {
+    fn from(encoding: CLITextEncoding) -> Self {
+        match encoding {
+            CLITextEncoding::Ascii => TextEncoding::Ascii,
+            CLITextEncoding::Utf8 => TextEncoding::Utf8,
+            CLITextEncoding::Utf16le => TextEncoding::Utf16Le,
+            CLITextEncoding::Utf16be => TextEncoding::Utf16Be,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIValidateOptions {
+    /// Report records that do not belong in the given file type.
+    #[arg(value_enum, long)]
+    pub expect_type: Option<CLIFileType>,
+
+    /// Treat validation warnings as errors.
+    #[arg(long)]
+    pub deny_warnings: bool,
+}
+
+impl From<&CLIValidateOptions> for ValidationOptions {
+    fn from(options: &CLIValidateOptions) -> Self {
+        ValidationOptions {
+            expected_filetype: options.expect_type.clone().map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIFileType {
+    Ihex8,
+    Ihex16,
+    Ihex32,
+}
+
+impl From<CLIFileType> for IHexFileType {
+    fn from(filetype: CLIFileType) -> Self {
+        match filetype {
+            CLIFileType::Ihex8 => IHexFileType::IHex8,
+            CLIFileType::Ihex16 => IHexFileType::IHex16,
+            CLIFileType::Ihex32 => IHexFileType::IHex32,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIWriteOptions {
+    /// Use lowercase hex digits when writing Intel HEX output.
+    #[arg(long)]
+    pub lowercase: bool,
+
+    /// Use CRLF line endings when writing text output.
+    #[arg(long)]
+    pub crlf: bool,
+}
+
+impl CLIWriteOptions {
+    pub fn line_ending(&self) -> LineEnding {
+        if self.crlf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+impl From<&CLIWriteOptions> for IHexWriteOptions {
+    fn from(options: &CLIWriteOptions) -> Self {
+        IHexWriteOptions {
+            case: if options.lowercase {
+                HexCase::Lower
+            } else {
+                HexCase::Upper
+            },
+            line_ending: options.line_ending(),
+        }
+    }
+}
+
+impl From<&CLIWriteOptions> for TiTxtWriteOptions {
+    fn from(options: &CLIWriteOptions) -> Self {
+        TiTxtWriteOptions {
+            line_ending: options.line_ending(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&CLIWriteOptions> for SRecordWriteOptions {
+    fn from(options: &CLIWriteOptions) -> Self {
+        SRecordWriteOptions {
+            line_ending: options.line_ending(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CLIBinaryOptions {
+    /// Treat the input file as a raw binary image loaded at the given address, instead of as an Intel HEX file.
+    #[arg(help_heading = "Binary input", long, value_parser = parse_addr)]
+    pub binary_base: Option<u32>,
+
+    /// The maximum amount of data bytes per generated data record.
+    #[arg(help_heading = "Binary input", long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(1..), requires = "binary_base")]
+    pub record_size: u8,
+
+    /// The kind of extended address records to generate.
+    #[arg(help_heading = "Binary input", value_enum, long, default_value_t = CLIAddressingMode::Linear, requires = "binary_base")]
+    pub addressing: CLIAddressingMode,
+
+    /// The start address to store in the generated file. Either a linear address, or a CS:IP pair.
+    #[arg(help_heading = "Binary input", long, value_parser = parse_start_addr, requires = "binary_base")]
+    pub start_address: Option<StartAddr>,
+}
+
+impl From<&CLIBinaryOptions> for BinaryImportOptions {
+    fn from(options: &CLIBinaryOptions) -> Self {
+        BinaryImportOptions {
+            record_size: options.record_size,
+            addressing: options.addressing.clone().into(),
+            start_address: options.start_address,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum CLIAddressingMode {
+    Segment,
+    Linear,
+}
+
+impl From<CLIAddressingMode> for AddressingMode {
+    fn from(mode: CLIAddressingMode) -> Self {
+        match mode {
+            CLIAddressingMode::Segment => AddressingMode::Segment,
+            CLIAddressingMode::Linear => AddressingMode::Linear,
+        }
+    }
+}
+
+fn parse_addr(value: &str) -> Result<u32, String> {
+    parse_address(value).map_err(|e| e.to_string())
+}
+
+fn parse_u8(value: &str) -> Result<u8, String> {
+    parse_number(value).map_err(|e| e.to_string())
+}
+
+/// Parses a start address. `CS:IP` pairs are kept as a segment start address, with the same
+/// syntax as [`parse_address`].
+fn parse_start_addr(value: &str) -> Result<StartAddr, String> {
+    match value.split_once(':') {
+        Some((cs, ip)) => {
+            let code_segment = u16::from_str_radix(cs.trim(), 16)
+                .map_err(|e| format!("Invalid code segment '{}': {}", cs, e))?;
+            let instruction_pointer = u16::from_str_radix(ip.trim(), 16)
+                .map_err(|e| format!("Invalid instruction pointer '{}': {}", ip, e))?;
+
+            Ok(StartAddr::Segment(SegmentStartAddr {
+                code_segment,
+                instruction_pointer,
+            }))
+        }
+        None => Ok(StartAddr::Linear(parse_addr(value)?)),
+    }
 }
 
 #[derive(Debug, Clone, ValueEnum)]
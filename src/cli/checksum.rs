@@ -0,0 +1,92 @@
+use std::{io::Write, ops::RangeInclusive};
+
+use serde::Serialize;
+
+use cpr_ihex::{
+    checksum::{memory_checksum, ChecksumAlgorithm},
+    memory::MemoryMap,
+};
+
+#[derive(Serialize)]
+struct ChecksumInfo {
+    algorithm: &'static str,
+    value: String,
+}
+
+/// The checksums over a range of a file, as printed by the `checksum` subcommand
+#[derive(Serialize)]
+pub struct ChecksumReport {
+    /// The first address of the range, or `None` if the range is empty
+    start: Option<u32>,
+    /// The last address of the range, inclusive
+    end: Option<u32>,
+    size: u64,
+    fill: u8,
+    checksums: Vec<ChecksumInfo>,
+}
+
+impl ChecksumReport {
+    /// Calculates each of `algorithms` over `range`. An empty range yields the
+    /// checksums of no data at all.
+    pub fn new(
+        memory: &MemoryMap,
+        algorithms: &[ChecksumAlgorithm],
+        range: Option<RangeInclusive<u32>>,
+        fill: u8,
+    ) -> Self {
+        let checksums = algorithms
+            .iter()
+            .map(|algorithm| ChecksumInfo {
+                algorithm: algorithm.name(),
+                value: memory_checksum(memory, *algorithm, range.clone(), fill).to_string(),
+            })
+            .collect();
+
+        let range = range.filter(|range| !range.is_empty());
+
+        ChecksumReport {
+            start: range.as_ref().map(|range| *range.start()),
+            end: range.as_ref().map(|range| *range.end()),
+            size: range.map_or(0, |range| {
+                (*range.end() as u64) - (*range.start() as u64) + 1
+            }),
+            fill,
+            checksums,
+        }
+    }
+
+    pub fn write_json(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, self)?;
+        writeln!(writer)
+    }
+
+    pub fn write_text(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => writeln!(
+                writer,
+                "Range: 0x{:08x}-0x{:08x} ({} bytes), fill byte 0x{:02x}",
+                start, end, self.size, self.fill
+            )?,
+            _ => writeln!(writer, "Range: empty")?,
+        }
+
+        let width = self
+            .checksums
+            .iter()
+            .map(|checksum| checksum.algorithm.len())
+            .max()
+            .unwrap_or(0);
+
+        for checksum in self.checksums.iter() {
+            writeln!(
+                writer,
+                "{:width$}  {}",
+                format!("{}:", checksum.algorithm),
+                checksum.value,
+                width = width + 1
+            )?;
+        }
+
+        Ok(())
+    }
+}
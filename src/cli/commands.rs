@@ -1,41 +1,623 @@
-use pretty_hex::pretty_hex;
+use pretty_hex::{config_hex, HexConfig};
 use std::{
     error::Error,
     fmt::{self, Display, Formatter},
-    io::{stdout, BufWriter, Write},
+    fs::File,
+    io::{stdout, BufRead, BufReader, BufWriter, Lines, Read, Stdout, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
 };
 
-use crate::record::file::IHexFile;
+use cpr_ihex::{
+    checksum::{insert_checksum, ChecksumAlgorithm, ChecksumInsertError, ChecksumInsertOptions},
+    formats::{
+        elf::{ElfFile, ElfReadError},
+        meminit::{write_meminit, MemInitError, MemInitFormat, MemInitOptions},
+        source::{write_source, SourceExportError, SourceLanguage, SourceWriteOptions},
+        srec::{SRecordFile, SRecordReadError},
+        titxt::{TiTxtFile, TiTxtReadError},
+        FileFormat,
+    },
+    memory::MemoryMap,
+    merge::{merge, MergeError},
+    record::{
+        binary::{BinaryImportError, BinaryImportOptions},
+        file::{IHexDiagnostic, IHexFile, IHexReadError},
+        validate::{Severity, ValidationOptions},
+        IHexRecord,
+    },
+    search::{PatternError, SearchPattern},
+};
+
+use super::{
+    args::{
+        CLIBinaryOptions, CLIChecksumArgs, CLIConvertArgs, CLIDiffArgs, CLIFindArgs, CLIInput,
+        CLIInsertChecksumArgs, CLIMergeArgs, CLIOutput, CLIOutputFormat, CLIReportFormat,
+        CLISubcommand,
+    },
+    checksum::ChecksumReport,
+    diff::DiffReport,
+    info::FileInfo,
+};
+
+/// A file read by [`read_file`]
+pub struct ReadFile {
+    pub file: IHexFile,
+    /// The records skipped while leniently reading an Intel HEX file
+    pub diagnostics: Vec<IHexDiagnostic>,
+    /// A description of every record skipped while leniently reading the file, in any format
+    pub skipped: Vec<String>,
+}
+
+impl ReadFile {
+    fn new(file: IHexFile) -> Self {
+        ReadFile {
+            file,
+            diagnostics: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+/// Reads a file in the given format, or in the format detected by [`FileFormat::detect`] if
+/// none is given.
+/// The file is read as a raw binary image instead if binary options with a base address are given.
+/// In lenient mode, invalid records are skipped and logged.
+pub fn read_file(
+    path: &Path,
+    format: Option<FileFormat>,
+    lenient: bool,
+    binary_options: Option<&CLIBinaryOptions>,
+) -> Result<ReadFile, RunCommandErr> {
+    if let Some(binary_options) = binary_options {
+        if let Some(base) = binary_options.binary_base {
+            let data = std::fs::read(path)?;
+            let file = IHexFile::from_binary(&data, base, &binary_options.into())?;
+
+            return Ok(ReadFile::new(file));
+        }
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let format = match format {
+        Some(format) => format,
+        None => FileFormat::detect(path, reader.fill_buf()?).unwrap_or_default(),
+    };
+    let options = BinaryImportOptions::default();
+
+    let read = match format {
+        FileFormat::IHex => return read_ihex(reader.lines(), lenient),
+        FileFormat::SRecord if lenient => {
+            let (file, errors) = SRecordFile::read_lenient(reader.lines());
+            ReadFile {
+                skipped: log_skipped(&errors),
+                ..ReadFile::new(file.to_ihex(&options)?)
+            }
+        }
+        FileFormat::SRecord => ReadFile::new(SRecordFile::read(reader.lines())?.to_ihex(&options)?),
+        FileFormat::TiTxt if lenient => {
+            let (file, errors) = TiTxtFile::read_lenient(reader.lines());
+            ReadFile {
+                skipped: log_skipped(&errors),
+                ..ReadFile::new(file.to_ihex(&options)?)
+            }
+        }
+        FileFormat::TiTxt => ReadFile::new(TiTxtFile::read(reader.lines())?.to_ihex(&options)?),
+        FileFormat::Elf => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+
+            ReadFile::new(ElfFile::read(&data)?.to_ihex(&options)?)
+        }
+    };
+
+    Ok(read)
+}
 
-use super::args::CLIArgs;
+/// Logs the errors of a lenient read, and returns their descriptions
+fn log_skipped<E: Display>(errors: &[E]) -> Vec<String> {
+    errors
+        .iter()
+        .map(|error| {
+            log::warn!("Skipped invalid record: {}", error);
+            error.to_string()
+        })
+        .collect()
+}
+
+fn read_ihex(lines: Lines<BufReader<File>>, lenient: bool) -> Result<ReadFile, RunCommandErr> {
+    if !lenient {
+        return Ok(ReadFile::new(IHexFile::read(lines)?));
+    }
+
+    let (file, diagnostics) = IHexFile::read_lenient(lines);
+    let errors: Vec<_> = diagnostics.iter().map(|d| &d.error).collect();
+
+    Ok(ReadFile {
+        file,
+        skipped: log_skipped(&errors),
+        diagnostics,
+    })
+}
+
+fn read_input(input: &CLIInput) -> Result<ReadFile, RunCommandErr> {
+    read_file(
+        &input.file,
+        input.from.clone().map(Into::into),
+        input.lenient,
+        Some(&input.binary_options),
+    )
+}
+
+/// Replaces the contents of the file at `path` with `data`. The data is written to a temporary
+/// file next to it first, which is then renamed over the file, so the file is never left
+/// half-written.
+pub fn replace_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    std::fs::write(&temp_path, data)?;
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
 
-fn run_bindump(file: &IHexFile) -> Result<(), std::io::Error> {
-    let mut writer = BufWriter::new(stdout());
+/// The output of a subcommand. Output to a file is kept in memory, and only written to the
+/// file by [`CommandOutput::finish`], so a failing command does not leave a truncated file behind
+enum CommandOutput {
+    Stdout(BufWriter<Stdout>),
+    File { path: PathBuf, buffer: Vec<u8> },
+}
+
+impl CommandOutput {
+    /// Writes the output to its file with [`replace_file`]
+    fn finish(self) -> Result<(), std::io::Error> {
+        match self {
+            CommandOutput::Stdout(mut writer) => writer.flush(),
+            CommandOutput::File { path, buffer } => replace_file(&path, &buffer),
+        }
+    }
 
-    writer.write_all(&file.data_bytes())?;
+    /// Finishes the output of a command. If the command failed, the output file is left
+    /// untouched, unless the failure is the result the command reports
+    fn finish_with(self, result: Result<(), RunCommandErr>) -> Result<(), RunCommandErr> {
+        match &result {
+            Ok(()) => self.finish()?,
+            Err(e) if e.is_verdict() => self.finish()?,
+            Err(_) => {}
+        }
+
+        result
+    }
+}
+
+impl Write for CommandOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CommandOutput::Stdout(writer) => writer.write(buf),
+            CommandOutput::File { buffer, .. } => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CommandOutput::Stdout(writer) => writer.flush(),
+            CommandOutput::File { .. } => Ok(()),
+        }
+    }
+}
+
+fn open_output(output: &CLIOutput) -> CommandOutput {
+    match &output.output {
+        Some(path) => CommandOutput::File {
+            path: path.clone(),
+            buffer: Vec::new(),
+        },
+        None => CommandOutput::Stdout(BufWriter::new(stdout())),
+    }
+}
+
+fn run_info(
+    input: &ReadFile,
+    format: &CLIReportFormat,
+    writer: &mut impl Write,
+) -> Result<(), std::io::Error> {
+    let info = FileInfo::new(&input.file, &input.skipped);
+
+    match format {
+        CLIReportFormat::Text => info.write_text(writer),
+        CLIReportFormat::Json => info.write_json(writer),
+    }
+}
+
+fn run_convert(
+    file: &IHexFile,
+    args: &CLIConvertArgs,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let source_options = |language| SourceWriteOptions {
+        language,
+        name: args.source_options.name.clone(),
+        columns: args.source_options.columns,
+        line_ending: args.write_options.line_ending(),
+    };
+
+    let meminit_options = |format| MemInitOptions {
+        format,
+        word_width: args.meminit_options.word_width,
+        endianness: args.meminit_options.endianness.clone().into(),
+        fill: args.fill,
+        base_address: args.meminit_options.base_address,
+        depth: args.meminit_options.depth,
+        line_ending: args.write_options.line_ending(),
+    };
+
+    match args.to {
+        CLIOutputFormat::Ihex => file.write(writer, &(&args.write_options).into())?,
+        CLIOutputFormat::Srec => {
+            SRecordFile::from_ihex(file).write(writer, &(&args.write_options).into())?
+        }
+        CLIOutputFormat::Titxt => {
+            if file.start_address().is_some() {
+                log::warn!("TI-TXT files can not store the start address, so it is discarded");
+            }
+
+            TiTxtFile::from_ihex(file).write(writer, &(&args.write_options).into())?
+        }
+        CLIOutputFormat::Binary => {
+            let memory = file.data_bytes();
+
+            if let Some(min) = memory.min_address() {
+                log::info!("Binary image starts at address 0x{:x}", min);
+            }
+
+            writer.write_all(&memory.to_flat(args.fill))?
+        }
+        CLIOutputFormat::C => write_source(
+            &file.data_bytes(),
+            writer,
+            &source_options(SourceLanguage::C),
+        )?,
+        CLIOutputFormat::Rust => write_source(
+            &file.data_bytes(),
+            writer,
+            &source_options(SourceLanguage::Rust),
+        )?,
+        CLIOutputFormat::Readmemh => write_meminit(
+            &file.data_bytes(),
+            writer,
+            &meminit_options(MemInitFormat::ReadMemH),
+        )?,
+        CLIOutputFormat::Coe => write_meminit(
+            &file.data_bytes(),
+            writer,
+            &meminit_options(MemInitFormat::Coe),
+        )?,
+        CLIOutputFormat::Mif => write_meminit(
+            &file.data_bytes(),
+            writer,
+            &meminit_options(MemInitFormat::Mif),
+        )?,
+    }
 
     Ok(())
 }
 
-fn run_hexdump(file: &IHexFile) {
-    println!("{}", pretty_hex(&file.data_bytes()));
+/// Validates a file. Records skipped while leniently reading it are reported as errors.
+fn run_validate(
+    input: &ReadFile,
+    options: &ValidationOptions,
+    deny_warnings: bool,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let issues = input.file.validate(options);
+
+    for skipped in input.skipped.iter() {
+        writeln!(
+            writer,
+            "{}: skipped invalid record: {}",
+            Severity::Error,
+            skipped
+        )?;
+    }
+
+    for issue in issues.iter() {
+        writeln!(writer, "{}", issue)?;
+    }
+
+    let issue_errors = issues
+        .iter()
+        .filter(|issue| issue.severity() == Severity::Error)
+        .count();
+    let errors = issue_errors + input.skipped.len();
+    let warnings = issues.len() - issue_errors;
+
+    writeln!(writer, "{} error(s), {} warning(s)", errors, warnings)?;
+    writer.flush()?;
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        return Err(RunCommandErr::ValidationFailed);
+    }
+
+    Ok(())
+}
+
+fn run_find(
+    file: &IHexFile,
+    args: &CLIFindArgs,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let pattern = match (&args.pattern, &args.string) {
+        (_, Some(string)) => SearchPattern::from_text(string, args.encoding.clone().into())?,
+        (Some(pattern), None) => SearchPattern::from_hex(pattern)?,
+        (None, None) => unreachable!("Clap requires either a pattern or a string"),
+    };
+
+    let memory = file.data_bytes();
+    let mut matches = 0;
+
+    for address in pattern.find_iter(&memory) {
+        writeln!(writer, "0x{:08x}", address)?;
+        matches += 1;
+    }
+
+    writer.flush()?;
+
+    log::info!("Found {} match(es)", matches);
+
+    if matches == 0 {
+        return Err(RunCommandErr::NoMatches);
+    }
+
+    Ok(())
+}
+
+/// Resolves the inclusive address range of a subcommand, where a missing start or end
+/// defaults to the lowest or highest address in the file. Returns `None` if there is
+/// no data to default to.
+fn address_range(
+    memory: &MemoryMap,
+    start: Option<u32>,
+    end: Option<u32>,
+) -> Result<Option<RangeInclusive<u32>>, RunCommandErr> {
+    let (Some(start), Some(end)) = (start.or(memory.min_address()), end.or(memory.max_address()))
+    else {
+        return Ok(None);
+    };
+
+    if start > end {
+        return Err(RunCommandErr::InvalidRange { start, end });
+    }
+
+    Ok(Some(start..=end))
+}
+
+fn run_checksum(
+    file: &IHexFile,
+    args: &CLIChecksumArgs,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let memory = file.data_bytes();
+    let range = address_range(&memory, args.start, args.end)?;
+
+    let algorithms: Vec<ChecksumAlgorithm> = match args.algorithm.is_empty() {
+        true => ChecksumAlgorithm::ALL.to_vec(),
+        false => args.algorithm.iter().cloned().map(Into::into).collect(),
+    };
+
+    let report = ChecksumReport::new(&memory, &algorithms, range, args.fill);
+
+    match args.format {
+        CLIReportFormat::Text => report.write_text(writer)?,
+        CLIReportFormat::Json => report.write_json(writer)?,
+    }
+
+    Ok(())
+}
+
+/// Stores a checksum in the file, and writes the result as Intel HEX. Created data records are
+/// as long as the longest data record in the file
+fn run_insert_checksum(
+    file: &IHexFile,
+    args: &CLIInsertChecksumArgs,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let memory = file.data_bytes();
+    let range = address_range(&memory, args.start, args.end)?;
+
+    let record_size = file
+        .records
+        .iter()
+        .filter_map(|record| match record {
+            IHexRecord::Data(data) => u8::try_from(data.data.len()).ok(),
+            _ => None,
+        })
+        .max()
+        .filter(|size| *size > 0)
+        .unwrap_or(16);
+
+    let options = ChecksumInsertOptions {
+        algorithm: args.algorithm.clone().into(),
+        range,
+        fill: args.fill,
+        endianness: args.endianness.clone().into(),
+        address: args.address,
+        record_size,
+    };
+
+    let (patched, value) = insert_checksum(file, &options)?;
+
+    log::info!(
+        "Inserted {} {} at 0x{:08x}",
+        options.algorithm.name(),
+        value,
+        options.address
+    );
+
+    patched.write(writer, &(&args.write_options).into())?;
+
+    Ok(())
+}
+
+/// Compares two files. The differences are only written if there are any
+fn run_diff(args: &CLIDiffArgs) -> Result<(), RunCommandErr> {
+    let a = read_file(&args.a, None, args.lenient, None)?.file;
+    let b = read_file(&args.b, None, args.lenient, None)?.file;
+
+    let report = DiffReport::new(&args.a, &a, &args.b, &b);
+    let mut writer = open_output(&args.output);
+
+    match args.format {
+        CLIReportFormat::Text => report.write_text(&mut writer)?,
+        CLIReportFormat::Json => report.write_json(&mut writer)?,
+    }
+
+    writer.finish()?;
+
+    if report.identical() {
+        log::info!("The files are identical");
+        return Ok(());
+    }
+
+    log::info!("{}", report.summary());
+
+    Err(RunCommandErr::FilesDiffer)
+}
+
+/// Reads and merges every input file. The output is always written as Intel HEX
+fn run_merge(args: &CLIMergeArgs) -> Result<(), RunCommandErr> {
+    let files = args
+        .files
+        .iter()
+        .map(|path| {
+            read_file(path, None, args.lenient, None)
+                .map(|read| read.file)
+                .inspect_err(|_| log::error!("Could not read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let merged = merge(&files, &args.into()).inspect_err(|e| {
+        if let Some((first, second)) = e.inputs() {
+            log::error!(
+                "Conflicting files: {} and {}",
+                args.files[first].display(),
+                args.files[second].display()
+            );
+        }
+    })?;
+
+    let mut writer = open_output(&args.output);
+
+    merged.write(&mut writer, &(&args.write_options).into())?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+fn run_dump(file: &IHexFile, writer: &mut impl Write) -> Result<(), std::io::Error> {
+    let memory = file.data_bytes();
+    let mut gaps = memory.gaps();
+
+    for segment in memory.segments() {
+        let config = HexConfig {
+            title: false,
+            display_offset: segment.start as usize,
+            ..Default::default()
+        };
+
+        writeln!(
+            writer,
+            "Segment 0x{:08x}-0x{:08x} ({} bytes)",
+            segment.start,
+            segment.end() - 1,
+            segment.data.len()
+        )?;
+        writeln!(writer, "{}\n", config_hex(&segment.data, config))?;
+
+        if let Some(gap) = gaps.next() {
+            writeln!(
+                writer,
+                "Gap 0x{:08x}-0x{:08x} ({} bytes)\n",
+                gap.start,
+                gap.end - 1,
+                gap.end - gap.start
+            )?;
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Debug)]
 pub enum RunCommandErr {
-    FileNotProvided,
     IoError(std::io::Error),
+    ReadError(IHexReadError),
+    SRecordReadError(SRecordReadError),
+    TiTxtReadError(TiTxtReadError),
+    ElfReadError(ElfReadError),
+    SourceExportError(SourceExportError),
+    MemInitError(MemInitError),
+    BinaryImportError(BinaryImportError),
+    MergeError(MergeError),
+    ChecksumInsertError(ChecksumInsertError),
+    InvalidPattern(PatternError),
+    ValidationFailed,
+    NoMatches,
+    FilesDiffer,
+    InvalidRange { start: u32, end: u32 },
 }
 
 impl Display for RunCommandErr {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            RunCommandErr::FileNotProvided => write!(f, "No file was provided to run commands on"),
             RunCommandErr::IoError(e) => write!(f, "An I/O error occurred: {}", e),
+            RunCommandErr::ReadError(e) => write!(f, "Could not read the file: {}", e),
+            RunCommandErr::SRecordReadError(e) => {
+                write!(f, "Could not read the S-record file: {}", e)
+            }
+            RunCommandErr::TiTxtReadError(e) => {
+                write!(f, "Could not read the TI-TXT file: {}", e)
+            }
+            RunCommandErr::ElfReadError(e) => write!(f, "Could not read the ELF file: {}", e),
+            RunCommandErr::SourceExportError(e) => {
+                write!(f, "Could not export the source code: {}", e)
+            }
+            RunCommandErr::MemInitError(e) => {
+                write!(f, "Could not export the memory initialization file: {}", e)
+            }
+            RunCommandErr::BinaryImportError(e) => {
+                write!(f, "Could not import the binary file: {}", e)
+            }
+            RunCommandErr::MergeError(e) => write!(f, "Could not merge the files: {}", e),
+            RunCommandErr::ChecksumInsertError(e) => {
+                write!(f, "Could not insert the checksum: {}", e)
+            }
+            RunCommandErr::InvalidPattern(e) => write!(f, "{}", e),
+            RunCommandErr::ValidationFailed => write!(f, "The file failed validation"),
+            RunCommandErr::NoMatches => write!(f, "No matches found"),
+            RunCommandErr::FilesDiffer => write!(f, "The files differ"),
+            RunCommandErr::InvalidRange { start, end } => write!(
+                f,
+                "The start address 0x{:08x} lies above the end address 0x{:08x}",
+                start, end
+            ),
         }
     }
 }
 
+impl RunCommandErr {
+    /// Whether the error is the outcome of a command that ran to completion and wrote a report
+    /// of it, such as the problems of a file that failed validation or an empty list of matches
+    fn is_verdict(&self) -> bool {
+        matches!(
+            self,
+            RunCommandErr::ValidationFailed | RunCommandErr::NoMatches | RunCommandErr::FilesDiffer
+        )
+    }
+}
+
 impl Error for RunCommandErr {}
 
 impl From<std::io::Error> for RunCommandErr {
@@ -44,25 +626,112 @@ impl From<std::io::Error> for RunCommandErr {
     }
 }
 
-fn check_file(file: Option<&IHexFile>) -> Result<&IHexFile, RunCommandErr> {
-    match file {
-        Some(f) => Ok(f),
-        None => Err(RunCommandErr::FileNotProvided),
+impl From<IHexReadError> for RunCommandErr {
+    fn from(e: IHexReadError) -> Self {
+        RunCommandErr::ReadError(e)
     }
 }
 
-/// If any commands were specified in the args, run them.
-/// Returns true if a command was run, false otherwise.
-pub fn run_commands(args: &CLIArgs, file: Option<&IHexFile>) -> Result<bool, RunCommandErr> {
-    if args.commands.hexdump {
-        run_hexdump(check_file(file)?);
-        return Ok(true);
+impl From<SRecordReadError> for RunCommandErr {
+    fn from(e: SRecordReadError) -> Self {
+        RunCommandErr::SRecordReadError(e)
     }
+}
+
+impl From<TiTxtReadError> for RunCommandErr {
+    fn from(e: TiTxtReadError) -> Self {
+        RunCommandErr::TiTxtReadError(e)
+    }
+}
 
-    if args.commands.bindump {
-        run_bindump(check_file(file)?)?;
-        return Ok(true);
+impl From<ElfReadError> for RunCommandErr {
+    fn from(e: ElfReadError) -> Self {
+        RunCommandErr::ElfReadError(e)
     }
+}
+
+impl From<SourceExportError> for RunCommandErr {
+    fn from(e: SourceExportError) -> Self {
+        RunCommandErr::SourceExportError(e)
+    }
+}
+
+impl From<MemInitError> for RunCommandErr {
+    fn from(e: MemInitError) -> Self {
+        RunCommandErr::MemInitError(e)
+    }
+}
 
-    Ok(false)
+impl From<BinaryImportError> for RunCommandErr {
+    fn from(e: BinaryImportError) -> Self {
+        RunCommandErr::BinaryImportError(e)
+    }
+}
+
+impl From<MergeError> for RunCommandErr {
+    fn from(e: MergeError) -> Self {
+        RunCommandErr::MergeError(e)
+    }
+}
+
+impl From<ChecksumInsertError> for RunCommandErr {
+    fn from(e: ChecksumInsertError) -> Self {
+        RunCommandErr::ChecksumInsertError(e)
+    }
+}
+
+impl From<PatternError> for RunCommandErr {
+    fn from(e: PatternError) -> Self {
+        RunCommandErr::InvalidPattern(e)
+    }
+}
+
+/// Runs the given subcommand, writing its output to stdout or the requested output file.
+pub fn run_subcommand(subcommand: &CLISubcommand) -> Result<(), RunCommandErr> {
+    let (input, output) = match subcommand {
+        CLISubcommand::Merge(args) => return run_merge(args),
+        CLISubcommand::Diff(args) => return run_diff(args),
+        CLISubcommand::Info(args) => (&args.input, &args.output),
+        CLISubcommand::Dump(args) => (&args.input, &args.output),
+        CLISubcommand::Convert(args) => (&args.input, &args.output),
+        CLISubcommand::Validate(args) => (&args.input, &args.output),
+        CLISubcommand::Find(args) => (&args.input, &args.output),
+        CLISubcommand::Checksum(args) => (&args.input, &args.output),
+        CLISubcommand::InsertChecksum(args) => (&args.input, &args.output),
+    };
+
+    let input = read_input(input)?;
+    let mut writer = open_output(output);
+    let result = run_file_subcommand(subcommand, &input, &mut writer);
+
+    writer.finish_with(result)
+}
+
+/// Runs a subcommand that reads a single file
+fn run_file_subcommand(
+    subcommand: &CLISubcommand,
+    input: &ReadFile,
+    writer: &mut impl Write,
+) -> Result<(), RunCommandErr> {
+    let file = &input.file;
+
+    match subcommand {
+        CLISubcommand::Info(args) => run_info(input, &args.format, writer)?,
+        CLISubcommand::Dump(_) => run_dump(file, writer)?,
+        CLISubcommand::Convert(args) => run_convert(file, args, writer)?,
+        CLISubcommand::Validate(args) => run_validate(
+            input,
+            &(&args.options).into(),
+            args.options.deny_warnings,
+            writer,
+        )?,
+        CLISubcommand::Find(args) => run_find(file, args, writer)?,
+        CLISubcommand::Checksum(args) => run_checksum(file, args, writer)?,
+        CLISubcommand::InsertChecksum(args) => run_insert_checksum(file, args, writer)?,
+        CLISubcommand::Merge(_) | CLISubcommand::Diff(_) => {
+            unreachable!("Merge and diff read their own input files")
+        }
+    }
+
+    Ok(())
 }
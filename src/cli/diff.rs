@@ -0,0 +1,189 @@
+use std::{io::Write, path::Path};
+
+use serde::{Serialize, Serializer};
+
+use cpr_ihex::{
+    diff::{DiffKind, ImageDiff},
+    record::file::IHexFile,
+};
+
+use super::info::StartAddressInfo;
+
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum DiffKindInfo {
+    Changed,
+    OnlyInA,
+    OnlyInB,
+}
+
+impl From<DiffKind> for DiffKindInfo {
+    fn from(kind: DiffKind) -> Self {
+        match kind {
+            DiffKind::Changed => DiffKindInfo::Changed,
+            DiffKind::OnlyInA => DiffKindInfo::OnlyInA,
+            DiffKind::OnlyInB => DiffKindInfo::OnlyInB,
+        }
+    }
+}
+
+fn serialize_hex<S: Serializer>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&hex::encode_upper(bytes)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Serialize)]
+struct DiffRangeInfo {
+    kind: DiffKindInfo,
+    start: u32,
+    /// The last address of the range, inclusive
+    end: u32,
+    size: u64,
+    /// The bytes of file A in the range, if it has data there
+    #[serde(serialize_with = "serialize_hex")]
+    a: Option<Vec<u8>>,
+    /// The bytes of file B in the range, if it has data there
+    #[serde(serialize_with = "serialize_hex")]
+    b: Option<Vec<u8>>,
+}
+
+#[derive(Serialize)]
+struct StartAddressDiff {
+    a: Option<StartAddressInfo>,
+    b: Option<StartAddressInfo>,
+    changed: bool,
+}
+
+/// The differences between two files, as printed by the `diff` subcommand
+#[derive(Serialize)]
+pub struct DiffReport {
+    a: String,
+    b: String,
+    identical: bool,
+    changed_bytes: u64,
+    only_in_a_bytes: u64,
+    only_in_b_bytes: u64,
+    ranges: Vec<DiffRangeInfo>,
+    start_address: StartAddressDiff,
+}
+
+impl DiffReport {
+    pub fn new(path_a: &Path, a: &IHexFile, path_b: &Path, b: &IHexFile) -> Self {
+        let diff = ImageDiff::new(a, b);
+        let (memory_a, memory_b) = (a.data_bytes(), b.data_bytes());
+
+        let ranges = diff
+            .ranges
+            .iter()
+            .map(|range| {
+                let start = range.range.start as u32;
+                let size = range.range.end - range.range.start;
+                let a = range.kind != DiffKind::OnlyInB;
+                let b = range.kind != DiffKind::OnlyInA;
+
+                DiffRangeInfo {
+                    kind: range.kind.into(),
+                    start,
+                    end: (range.range.end - 1) as u32,
+                    size,
+                    a: a.then(|| memory_a.read(start, size as usize, 0)),
+                    b: b.then(|| memory_b.read(start, size as usize, 0)),
+                }
+            })
+            .collect();
+
+        DiffReport {
+            a: path_a.display().to_string(),
+            b: path_b.display().to_string(),
+            identical: diff.is_empty(),
+            changed_bytes: diff.byte_count(DiffKind::Changed),
+            only_in_a_bytes: diff.byte_count(DiffKind::OnlyInA),
+            only_in_b_bytes: diff.byte_count(DiffKind::OnlyInB),
+            ranges,
+            start_address: StartAddressDiff {
+                a: diff.start_address_a.map(Into::into),
+                b: diff.start_address_b.map(Into::into),
+                changed: diff.start_address_changed(),
+            },
+        }
+    }
+
+    pub fn identical(&self) -> bool {
+        self.identical
+    }
+
+    /// A one-line summary of the amount of differing bytes
+    pub fn summary(&self) -> String {
+        format!(
+            "{} byte(s) changed, {} byte(s) only in {}, {} byte(s) only in {}",
+            self.changed_bytes, self.only_in_a_bytes, self.a, self.only_in_b_bytes, self.b
+        )
+    }
+
+    pub fn write_json(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, self)?;
+        writeln!(writer)
+    }
+
+    /// Writes the differences in the style of a unified diff, with one hunk per range
+    pub fn write_text(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        if self.identical {
+            return Ok(());
+        }
+
+        writeln!(writer, "--- {}", self.a)?;
+        writeln!(writer, "+++ {}", self.b)?;
+
+        for range in self.ranges.iter() {
+            let size_a = if range.a.is_some() { range.size } else { 0 };
+            let size_b = if range.b.is_some() { range.size } else { 0 };
+            let description = match range.kind {
+                DiffKindInfo::Changed => "changed",
+                DiffKindInfo::OnlyInA => "only in A",
+                DiffKindInfo::OnlyInB => "only in B",
+            };
+
+            writeln!(
+                writer,
+                "@@ -0x{:08x},{} +0x{:08x},{} @@ {}",
+                range.start, size_a, range.start, size_b, description
+            )?;
+
+            for (prefix, bytes) in [('-', &range.a), ('+', &range.b)] {
+                let Some(bytes) = bytes else {
+                    continue;
+                };
+
+                for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+                    let line_bytes: Vec<String> =
+                        line.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+                    writeln!(
+                        writer,
+                        "{}{:08x}: {}",
+                        prefix,
+                        range.start as usize + i * BYTES_PER_LINE,
+                        line_bytes.join(" ")
+                    )?;
+                }
+            }
+        }
+
+        if self.start_address.changed {
+            writeln!(writer, "@@ start address @@")?;
+
+            for (prefix, start) in [('-', &self.start_address.a), ('+', &self.start_address.b)] {
+                match start {
+                    Some(start) => writeln!(writer, "{}{}", prefix, start)?,
+                    None => writeln!(writer, "{}none", prefix)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
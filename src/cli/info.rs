@@ -0,0 +1,207 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Write,
+};
+
+use serde::Serialize;
+
+use cpr_ihex::{
+    checksum::{memory_checksum, ChecksumAlgorithm, ChecksumValue},
+    record::{
+        file::{IHexFile, IHexFileType, StartAddr},
+        IHexRecord,
+    },
+};
+
+/// The fill byte for gaps in the CRC-32, matching the default of the `checksum` subcommand
+const CRC32_FILL: u8 = 0xFF;
+
+#[derive(Serialize, Default)]
+struct RecordCounts {
+    total: usize,
+    data: usize,
+    end_of_file: usize,
+    extended_segment_address: usize,
+    start_segment_address: usize,
+    extended_linear_address: usize,
+    start_linear_address: usize,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(super) enum StartAddressInfo {
+    Segment {
+        code_segment: u16,
+        instruction_pointer: u16,
+    },
+    Linear {
+        address: u32,
+    },
+}
+
+impl From<StartAddr> for StartAddressInfo {
+    fn from(start: StartAddr) -> Self {
+        match start {
+            StartAddr::Segment(addr) => StartAddressInfo::Segment {
+                code_segment: addr.code_segment,
+                instruction_pointer: addr.instruction_pointer,
+            },
+            StartAddr::Linear(address) => StartAddressInfo::Linear { address },
+        }
+    }
+}
+
+impl Display for StartAddressInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StartAddressInfo::Segment {
+                code_segment,
+                instruction_pointer,
+            } => write!(f, "{:04x}:{:04x}", code_segment, instruction_pointer),
+            StartAddressInfo::Linear { address } => write!(f, "0x{:08x}", address),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RegionInfo {
+    start: u32,
+    /// The last address of the region, inclusive
+    end: u32,
+    size: usize,
+}
+
+/// A summary of a file, as printed by the `info` subcommand
+#[derive(Serialize)]
+pub struct FileInfo {
+    file_type: &'static str,
+    records: RecordCounts,
+    start_address: Option<StartAddressInfo>,
+    min_address: Option<u32>,
+    max_address: Option<u32>,
+    data_bytes: u64,
+    regions: Vec<RegionInfo>,
+    /// CRC-32 from the lowest to the highest address, with gaps filled with [`CRC32_FILL`],
+    /// as calculated by the `checksum` subcommand
+    crc32: u32,
+    /// The records skipped while leniently reading the file
+    skipped_records: Vec<String>,
+}
+
+impl FileInfo {
+    pub fn new(file: &IHexFile, skipped: &[String]) -> Self {
+        let memory = file.data_bytes();
+
+        let mut records = RecordCounts {
+            total: file.records.len(),
+            ..Default::default()
+        };
+
+        for record in file.records.iter() {
+            let count = match record {
+                IHexRecord::Data(_) => &mut records.data,
+                IHexRecord::EndOfFile(_) => &mut records.end_of_file,
+                IHexRecord::ExtendedSegmentAddress(_) => &mut records.extended_segment_address,
+                IHexRecord::StartSegmentAddress(_) => &mut records.start_segment_address,
+                IHexRecord::ExtendedLinearAddress(_) => &mut records.extended_linear_address,
+                IHexRecord::StartLinearAddress(_) => &mut records.start_linear_address,
+            };
+
+            *count += 1;
+        }
+
+        let crc32 = match memory_checksum(&memory, ChecksumAlgorithm::Crc32, None, CRC32_FILL) {
+            ChecksumValue::U32(crc) => crc,
+            _ => unreachable!("CRC-32 is a 32-bit checksum"),
+        };
+
+        FileInfo {
+            file_type: match file.filetype() {
+                IHexFileType::IHex8 => "ihex8",
+                IHexFileType::IHex16 => "ihex16",
+                IHexFileType::IHex32 => "ihex32",
+            },
+            records,
+            start_address: file.start_address().map(Into::into),
+            min_address: memory.min_address(),
+            max_address: memory.max_address(),
+            data_bytes: memory.len(),
+            regions: memory
+                .segments()
+                .iter()
+                .map(|segment| RegionInfo {
+                    start: segment.start,
+                    end: (segment.end() - 1) as u32,
+                    size: segment.data.len(),
+                })
+                .collect(),
+            crc32,
+            skipped_records: skipped.to_vec(),
+        }
+    }
+
+    pub fn write_json(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, self)?;
+        writeln!(writer)
+    }
+
+    pub fn write_text(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let records = &self.records;
+
+        writeln!(writer, "File type: {}", self.file_type)?;
+        writeln!(writer, "Records: {}", records.total)?;
+
+        let counts = [
+            ("Data", records.data),
+            ("End of file", records.end_of_file),
+            ("Extended segment address", records.extended_segment_address),
+            ("Start segment address", records.start_segment_address),
+            ("Extended linear address", records.extended_linear_address),
+            ("Start linear address", records.start_linear_address),
+        ];
+
+        for (name, count) in counts.iter().filter(|(_, count)| *count > 0) {
+            writeln!(writer, "  {}: {}", name, count)?;
+        }
+
+        match &self.start_address {
+            Some(start) => writeln!(writer, "Start address: {}", start)?,
+            None => writeln!(writer, "Start address: none")?,
+        }
+
+        if let (Some(min), Some(max)) = (self.min_address, self.max_address) {
+            writeln!(writer, "Address range: 0x{:08x}-0x{:08x}", min, max)?;
+        }
+
+        writeln!(
+            writer,
+            "Data: {} bytes in {} region(s)",
+            self.data_bytes,
+            self.regions.len()
+        )?;
+
+        for region in self.regions.iter() {
+            writeln!(
+                writer,
+                "  0x{:08x}-0x{:08x} ({} bytes)",
+                region.start, region.end, region.size
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "CRC-32 (gaps filled with 0x{:02X}): 0x{:08X}",
+            CRC32_FILL, self.crc32
+        )?;
+
+        if !self.skipped_records.is_empty() {
+            writeln!(writer, "Skipped records: {}", self.skipped_records.len())?;
+
+            for skipped in self.skipped_records.iter() {
+                writeln!(writer, "  {}", skipped)?;
+            }
+        }
+
+        Ok(())
+    }
+}
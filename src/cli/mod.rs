@@ -1,2 +1,5 @@
 pub mod args;
+pub mod checksum;
 pub mod commands;
+pub mod diff;
+pub mod info;
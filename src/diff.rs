@@ -0,0 +1,233 @@
+//! Comparing the data of two files by effective address, regardless of how the data is
+//! split into records.
+
+use std::ops::Range;
+
+use crate::{
+    memory::MemoryMap,
+    record::file::{IHexFile, StartAddr},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// The range is mapped in both images, but contains different bytes.
+    Changed,
+    /// The range is only mapped in the first image.
+    OnlyInA,
+    /// The range is only mapped in the second image.
+    OnlyInB,
+}
+
+/// A range of addresses where two images differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRange {
+    pub kind: DiffKind,
+    pub range: Range<u64>,
+}
+
+/// The differences between two images, A and B.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// The differing ranges in address order. Adjacent ranges always have a different kind.
+    pub ranges: Vec<DiffRange>,
+    pub start_address_a: Option<StartAddr>,
+    pub start_address_b: Option<StartAddr>,
+}
+
+impl ImageDiff {
+    /// Compares the data and start addresses of two files.
+    pub fn new(a: &IHexFile, b: &IHexFile) -> Self {
+        ImageDiff {
+            start_address_a: a.start_address(),
+            start_address_b: b.start_address(),
+            ..Self::from_memory(&a.data_bytes(), &b.data_bytes())
+        }
+    }
+
+    /// Compares two memory images. The start addresses are left empty.
+    pub fn from_memory(a: &MemoryMap, b: &MemoryMap) -> Self {
+        let mut diff = ImageDiff::default();
+
+        // Between two consecutive boundaries, both images are either fully mapped or unmapped
+        let mut boundaries: Vec<u64> = a
+            .segments()
+            .iter()
+            .chain(b.segments())
+            .flat_map(|seg| [seg.start as u64, seg.end()])
+            .collect();
+
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        for window in boundaries.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let address = start as u32;
+
+            match (a.segment_at(address), b.segment_at(address)) {
+                (Some(_), Some(_)) => {
+                    let len = (end - start) as usize;
+                    let bytes_a = a.read(address, len, 0);
+                    let bytes_b = b.read(address, len, 0);
+
+                    for (offset, (x, y)) in bytes_a.iter().zip(bytes_b.iter()).enumerate() {
+                        if x != y {
+                            let byte = start + offset as u64;
+                            diff.push(DiffKind::Changed, byte..byte + 1);
+                        }
+                    }
+                }
+                (Some(_), None) => diff.push(DiffKind::OnlyInA, start..end),
+                (None, Some(_)) => diff.push(DiffKind::OnlyInB, start..end),
+                (None, None) => {}
+            }
+        }
+
+        diff
+    }
+
+    /// Adds a range, extending the last range if it has the same kind and ends at `range.start`
+    fn push(&mut self, kind: DiffKind, range: Range<u64>) {
+        match self.ranges.last_mut() {
+            Some(last) if last.kind == kind && last.range.end == range.start => {
+                last.range.end = range.end
+            }
+            _ => self.ranges.push(DiffRange { kind, range }),
+        }
+    }
+
+    pub fn start_address_changed(&self) -> bool {
+        self.start_address_a != self.start_address_b
+    }
+
+    /// Whether the images have the same data and start address.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty() && !self.start_address_changed()
+    }
+
+    /// The kind of difference at `address`, or `None` if the images do not differ there.
+    pub fn kind_at(&self, address: u32) -> Option<DiffKind> {
+        let idx = self
+            .ranges
+            .partition_point(|diff| diff.range.end <= address as u64);
+
+        self.ranges
+            .get(idx)
+            .filter(|diff| diff.range.start <= address as u64)
+            .map(|diff| diff.kind)
+    }
+
+    /// The total amount of bytes in ranges of the given kind.
+    pub fn byte_count(&self, kind: DiffKind) -> u64 {
+        self.ranges
+            .iter()
+            .filter(|diff| diff.kind == kind)
+            .map(|diff| diff.range.end - diff.range.start)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::binary::BinaryImportOptions;
+
+    fn memory(segments: &[(u32, &[u8])]) -> MemoryMap {
+        let mut memory = MemoryMap::new();
+
+        for (start, data) in segments {
+            memory.insert(*start, data);
+        }
+
+        memory
+    }
+
+    fn range(kind: DiffKind, range: Range<u64>) -> DiffRange {
+        DiffRange { kind, range }
+    }
+
+    #[test]
+    fn diff_ranges() {
+        let a = memory(&[(0x00, &[1, 2, 3, 4, 5]), (0x10, &[7, 7])]);
+        let b = memory(&[(0x00, &[1, 9, 9, 4, 6, 8]), (0x11, &[7, 7])]);
+        let diff = ImageDiff::from_memory(&a, &b);
+
+        // Adjacent differing bytes are merged into one range, up to a change of kind
+        assert_eq!(
+            diff.ranges,
+            [
+                range(DiffKind::Changed, 0x01..0x03),
+                range(DiffKind::Changed, 0x04..0x05),
+                range(DiffKind::OnlyInB, 0x05..0x06),
+                range(DiffKind::OnlyInA, 0x10..0x11),
+                range(DiffKind::OnlyInB, 0x12..0x13),
+            ]
+        );
+
+        assert_eq!(diff.kind_at(0x02), Some(DiffKind::Changed));
+        assert_eq!(diff.kind_at(0x03), None);
+        assert_eq!(diff.kind_at(0x05), Some(DiffKind::OnlyInB));
+        assert_eq!(diff.kind_at(0x11), None);
+        assert_eq!(diff.kind_at(0x12), Some(DiffKind::OnlyInB));
+        assert_eq!(diff.kind_at(0xFFFF_FFFF), None);
+
+        assert_eq!(diff.byte_count(DiffKind::Changed), 3);
+        assert_eq!(diff.byte_count(DiffKind::OnlyInA), 1);
+        assert_eq!(diff.byte_count(DiffKind::OnlyInB), 2);
+
+        // Swapping the images swaps the kinds
+        let reversed = ImageDiff::from_memory(&b, &a);
+        assert_eq!(reversed.kind_at(0x05), Some(DiffKind::OnlyInA));
+        assert_eq!(reversed.byte_count(DiffKind::OnlyInB), 1);
+    }
+
+    #[test]
+    fn only_in_one_image() {
+        let a = memory(&[(0x00, &[1, 2])]);
+        let b = memory(&[(0x00, &[1, 2]), (0x0000_FFFF, &[3, 4])]);
+        let diff = ImageDiff::from_memory(&a, &b);
+
+        assert_eq!(diff.ranges, [range(DiffKind::OnlyInB, 0xFFFF..0x1_0001)]);
+        assert!(ImageDiff::from_memory(&MemoryMap::new(), &MemoryMap::new()).is_empty());
+    }
+
+    #[test]
+    fn record_layout_is_ignored() {
+        let memory = memory(&[(0x0800_0000, &[0xAB; 100])]);
+        let file = |record_size| {
+            let options = BinaryImportOptions {
+                record_size,
+                ..Default::default()
+            };
+
+            IHexFile::from_memory_map(&memory, &options).unwrap()
+        };
+
+        let (a, b) = (file(16), file(7));
+
+        assert_ne!(a.records.len(), b.records.len());
+        assert!(ImageDiff::new(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn start_address_changes() {
+        let memory = memory(&[(0, &[1])]);
+        let file = |start_address| {
+            let options = BinaryImportOptions {
+                start_address,
+                ..Default::default()
+            };
+
+            IHexFile::from_memory_map(&memory, &options).unwrap()
+        };
+
+        let diff = ImageDiff::new(&file(None), &file(Some(StartAddr::Linear(0x100))));
+
+        assert!(diff.ranges.is_empty());
+        assert!(diff.start_address_changed());
+        assert!(!diff.is_empty());
+        assert!(matches!(
+            diff.start_address_b,
+            Some(StartAddr::Linear(0x100))
+        ));
+    }
+}
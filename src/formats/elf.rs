@@ -0,0 +1,516 @@
+//! Loading the program image of ELF32 and ELF64 executables.
+//!
+//! Like `objcopy -O ihex`, the file contents of every `PT_LOAD` segment are placed at
+//! its physical address. Zero-initialized memory that is not stored in the file (such
+//! as `.bss`) is not included.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    memory::MemoryMap,
+    record::{
+        binary::{BinaryImportError, BinaryImportOptions},
+        file::IHexFile,
+    },
+};
+
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const PT_LOAD: u32 = 1;
+const PHDR_SIZE_32: u16 = 32;
+const PHDR_SIZE_64: u16 = 56;
+
+/// Reads the fields of an ELF file, in its class and byte order
+struct ElfReader<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl ElfReader<'_> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Result<[u8; N], ElfReadError> {
+        let start = usize::try_from(offset).map_err(|_| ElfReadError::Truncated)?;
+        let end = start.checked_add(N).ok_or(ElfReadError::Truncated)?;
+
+        self.data
+            .get(start..end)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(ElfReadError::Truncated)
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ElfReadError> {
+        let bytes = self.bytes(offset)?;
+
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ElfReadError> {
+        let bytes = self.bytes(offset)?;
+
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ElfReadError> {
+        let bytes = self.bytes(offset)?;
+
+        Ok(match self.big_endian {
+            true => u64::from_be_bytes(bytes),
+            false => u64::from_le_bytes(bytes),
+        })
+    }
+
+    /// The offset of a field at `field` bytes into the structure at `base`
+    fn field(base: u64, field: u64) -> Result<u64, ElfReadError> {
+        base.checked_add(field).ok_or(ElfReadError::Truncated)
+    }
+
+    /// Reads an address or offset, which is 32 or 64 bits wide depending on the class
+    fn word(&self, offset: u64) -> Result<u64, ElfReadError> {
+        match self.is_64 {
+            true => self.u64(offset),
+            false => self.u32(offset).map(u64::from),
+        }
+    }
+}
+
+/// The loadable contents of an ELF executable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElfFile {
+    pub memory: MemoryMap,
+    /// The entry point of the executable. An entry point of 0 is read as no entry point,
+    /// as the ELF specification uses it for files without one.
+    pub entry_point: Option<u32>,
+}
+
+impl ElfFile {
+    /// Parses an ELF file and loads its `PT_LOAD` segments at their physical addresses.
+    /// Where segments overlap, later segments overwrite earlier ones.
+    pub fn read(data: &[u8]) -> Result<Self, ElfReadError> {
+        if !data.starts_with(&ELF_MAGIC) {
+            return Err(ElfReadError::NotElf);
+        }
+
+        let is_64 = match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            Some(class) => return Err(ElfReadError::UnsupportedClass(*class)),
+            None => return Err(ElfReadError::Truncated),
+        };
+
+        let big_endian = match data.get(5) {
+            Some(1) => false,
+            Some(2) => true,
+            Some(encoding) => return Err(ElfReadError::UnsupportedEncoding(*encoding)),
+            None => return Err(ElfReadError::Truncated),
+        };
+
+        let reader = ElfReader {
+            data,
+            is_64,
+            big_endian,
+        };
+
+        let (phoff, phentsize, phnum) = match is_64 {
+            true => (reader.u64(32)?, reader.u16(54)?, reader.u16(56)?),
+            false => (reader.u32(28)? as u64, reader.u16(42)?, reader.u16(44)?),
+        };
+
+        let min_phentsize = match is_64 {
+            true => PHDR_SIZE_64,
+            false => PHDR_SIZE_32,
+        };
+
+        if phnum > 0 && phentsize < min_phentsize {
+            return Err(ElfReadError::InvalidProgramHeaderSize(phentsize));
+        }
+
+        let entry = reader.word(24)?;
+        let entry_point = match u32::try_from(entry) {
+            Ok(0) => None,
+            Ok(entry) => Some(entry),
+            Err(_) => return Err(ElfReadError::EntryPointOutOfRange(entry)),
+        };
+
+        let mut memory = MemoryMap::new();
+
+        for index in 0..phnum as u64 {
+            let header = (phentsize as u64)
+                .checked_mul(index)
+                .and_then(|offset| phoff.checked_add(offset))
+                .ok_or(ElfReadError::Truncated)?;
+
+            if reader.u32(header)? != PT_LOAD {
+                continue;
+            }
+
+            let (offset, paddr, filesz) = match is_64 {
+                true => (
+                    reader.u64(ElfReader::field(header, 8)?)?,
+                    reader.u64(ElfReader::field(header, 24)?)?,
+                    reader.u64(ElfReader::field(header, 32)?)?,
+                ),
+                false => (
+                    reader.u32(ElfReader::field(header, 4)?)? as u64,
+                    reader.u32(ElfReader::field(header, 12)?)? as u64,
+                    reader.u32(ElfReader::field(header, 16)?)? as u64,
+                ),
+            };
+
+            if filesz == 0 {
+                continue;
+            }
+
+            let segment = offset
+                .checked_add(filesz)
+                .and_then(|end| data.get(offset as usize..usize::try_from(end).ok()?))
+                .ok_or(ElfReadError::SegmentOutOfFile { index })?;
+
+            if paddr.saturating_add(filesz) > 1 << 32 {
+                return Err(ElfReadError::AddressOutOfRange { index, paddr });
+            }
+
+            memory.insert(paddr as u32, segment);
+        }
+
+        Ok(ElfFile {
+            memory,
+            entry_point,
+        })
+    }
+
+    /// Generates an Intel HEX file with the same data, and the entry point as the start
+    /// address. `options.start_address` is only used if the executable has no entry point.
+    pub fn to_ihex(&self, options: &BinaryImportOptions) -> Result<IHexFile, BinaryImportError> {
+        let start_address = match self.entry_point {
+            Some(entry) => Some(options.addressing.start_address(entry)?),
+            None => options.start_address,
+        };
+
+        IHexFile::from_memory_map(
+            &self.memory,
+            &BinaryImportOptions {
+                start_address,
+                ..*options
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElfReadError {
+    NotElf,
+    UnsupportedClass(u8),
+    UnsupportedEncoding(u8),
+    Truncated,
+    /// The program header entry size is smaller than a program header of the file's class.
+    InvalidProgramHeaderSize(u16),
+    /// The program header at `index` refers to data past the end of the file.
+    SegmentOutOfFile {
+        index: u64,
+    },
+    /// The segment of the program header at `index` does not fit in 32-bit addresses.
+    AddressOutOfRange {
+        index: u64,
+        paddr: u64,
+    },
+    EntryPointOutOfRange(u64),
+}
+
+impl Display for ElfReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ElfReadError::NotElf => write!(f, "The file is not an ELF file"),
+            ElfReadError::UnsupportedClass(class) => {
+                write!(f, "Unsupported ELF class {}", class)
+            }
+            ElfReadError::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported ELF data encoding {}", encoding)
+            }
+            ElfReadError::Truncated => write!(f, "The ELF file is truncated"),
+            ElfReadError::InvalidProgramHeaderSize(size) => {
+                write!(f, "Invalid program header entry size {}", size)
+            }
+            ElfReadError::SegmentOutOfFile { index } => {
+                write!(f, "Segment {} extends past the end of the file", index)
+            }
+            ElfReadError::AddressOutOfRange { index, paddr } => write!(
+                f,
+                "Segment {} at physical address 0x{:x} does not fit in the 32-bit address space",
+                index, paddr
+            ),
+            ElfReadError::EntryPointOutOfRange(entry) => write!(
+                f,
+                "Entry point 0x{:x} does not fit in the 32-bit address space",
+                entry
+            ),
+        }
+    }
+}
+
+impl Error for ElfReadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::file::StartAddr;
+
+    /// A segment of a generated ELF file: type, physical address and file contents
+    type Segment<'a> = (u32, u64, &'a [u8]);
+
+    /// Builds an ELF file with one program header per segment, followed by their data.
+    /// Every segment gets a virtual address that differs from its physical address, and
+    /// a memory size larger than its file size.
+    fn build_elf(is_64: bool, big_endian: bool, entry: u64, segments: &[Segment]) -> Vec<u8> {
+        let put = |out: &mut Vec<u8>, offset: usize, value: u64, len: usize| {
+            let bytes = match big_endian {
+                true => value.to_be_bytes()[8 - len..].to_vec(),
+                false => value.to_le_bytes()[..len].to_vec(),
+            };
+
+            out[offset..offset + len].copy_from_slice(&bytes);
+        };
+
+        let (header_size, phentsize, word) = match is_64 {
+            true => (64, 56, 8),
+            false => (52, 32, 4),
+        };
+
+        let mut out = vec![0; header_size + phentsize * segments.len()];
+        out[..4].copy_from_slice(&ELF_MAGIC);
+        out[4] = if is_64 { 2 } else { 1 };
+        out[5] = if big_endian { 2 } else { 1 };
+        put(&mut out, 24, entry, word);
+
+        let (phoff, phentsize_offset) = match is_64 {
+            true => (32, 54),
+            false => (28, 42),
+        };
+
+        put(&mut out, phoff, header_size as u64, word);
+        put(&mut out, phentsize_offset, phentsize as u64, 2);
+        put(&mut out, phentsize_offset + 2, segments.len() as u64, 2);
+
+        for (i, (p_type, paddr, data)) in segments.iter().enumerate() {
+            let header = header_size + i * phentsize;
+            let offset = out.len() as u64;
+            let size = data.len() as u64;
+            let vaddr = paddr ^ 0x8000_0000;
+
+            put(&mut out, header, *p_type as u64, 4);
+
+            match is_64 {
+                true => {
+                    put(&mut out, header + 8, offset, 8);
+                    put(&mut out, header + 16, vaddr, 8);
+                    put(&mut out, header + 24, *paddr, 8);
+                    put(&mut out, header + 32, size, 8);
+                    put(&mut out, header + 40, size + 0x100, 8);
+                }
+                false => {
+                    put(&mut out, header + 4, offset, 4);
+                    put(&mut out, header + 8, vaddr, 4);
+                    put(&mut out, header + 12, *paddr, 4);
+                    put(&mut out, header + 16, size, 4);
+                    put(&mut out, header + 20, size + 0x100, 4);
+                }
+            }
+
+            out.extend_from_slice(data);
+        }
+
+        out
+    }
+
+    #[test]
+    fn read_elf32() {
+        let data = build_elf(
+            false,
+            false,
+            0x0800_0101,
+            &[
+                (PT_LOAD, 0x0800_0000, &[1, 2, 3, 4]),
+                (4, 0x2000_0000, &[0xEE; 4]),
+                (PT_LOAD, 0x0800_0100, &[5, 6]),
+            ],
+        );
+
+        let elf = ElfFile::read(&data).unwrap();
+
+        assert_eq!(elf.entry_point, Some(0x0800_0101));
+        assert_eq!(elf.memory.segments().len(), 2);
+        assert_eq!(elf.memory.read(0x0800_0000, 4, 0), [1, 2, 3, 4]);
+        assert_eq!(elf.memory.read(0x0800_0100, 2, 0), [5, 6]);
+    }
+
+    #[test]
+    fn read_elf64_big_endian() {
+        let data = build_elf(true, true, 0, &[(PT_LOAD, 0x1000, b"abc")]);
+
+        let elf = ElfFile::read(&data).unwrap();
+
+        assert_eq!(elf.entry_point, None);
+        assert_eq!(elf.memory.read(0x1000, 3, 0), b"abc");
+    }
+
+    #[test]
+    fn segment_placement() {
+        // Only the file contents are loaded, at the physical address
+        let data = build_elf(true, false, 0, &[(PT_LOAD, 0x2000, &[1, 2, 3])]);
+        let elf = ElfFile::read(&data).unwrap();
+
+        assert_eq!(elf.memory.min_address(), Some(0x2000));
+        assert_eq!(elf.memory.len(), 3);
+
+        // Segments without file contents are skipped, and later segments overwrite
+        // earlier ones
+        let data = build_elf(
+            false,
+            true,
+            0,
+            &[
+                (PT_LOAD, 0x100, &[1, 2, 3, 4]),
+                (PT_LOAD, 0x4000, &[]),
+                (PT_LOAD, 0x102, &[0xAA]),
+            ],
+        );
+        let elf = ElfFile::read(&data).unwrap();
+
+        assert_eq!(elf.memory.segments().len(), 1);
+        assert_eq!(elf.memory.read(0x100, 4, 0), [1, 2, 0xAA, 4]);
+
+        // The last byte of the address space can be loaded
+        let data = build_elf(false, false, 0, &[(PT_LOAD, 0xFFFF_FFFF, &[1])]);
+
+        assert_eq!(
+            ElfFile::read(&data).unwrap().memory.get(0xFFFF_FFFF),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn ihex_conversion() {
+        let data = build_elf(false, true, 0x1000, &[(PT_LOAD, 0x1_0000, &[0xAB; 20])]);
+        let elf = ElfFile::read(&data).unwrap();
+        let ihex = elf.to_ihex(&BinaryImportOptions::default()).unwrap();
+
+        assert_eq!(ihex.data_bytes(), elf.memory);
+        assert_eq!(
+            ihex.start_address().map(|start| start.linear_address()),
+            Some(0x1000)
+        );
+
+        // Without an entry point, the start address from the options is used
+        let data = build_elf(false, true, 0, &[(PT_LOAD, 0x1_0000, &[0xAB; 20])]);
+        let elf = ElfFile::read(&data).unwrap();
+        let options = BinaryImportOptions {
+            start_address: Some(StartAddr::Linear(0x2000)),
+            ..Default::default()
+        };
+        let ihex = elf.to_ihex(&options).unwrap();
+
+        assert_eq!(
+            ihex.start_address().map(|start| start.linear_address()),
+            Some(0x2000)
+        );
+    }
+
+    #[test]
+    fn invalid_headers() {
+        assert_eq!(ElfFile::read(b""), Err(ElfReadError::NotElf));
+        assert_eq!(ElfFile::read(b"\x7fELX"), Err(ElfReadError::NotElf));
+        assert_eq!(ElfFile::read(b"\x7fELF"), Err(ElfReadError::Truncated));
+        assert_eq!(
+            ElfFile::read(b"\x7fELF\x03\x01"),
+            Err(ElfReadError::UnsupportedClass(3))
+        );
+        assert_eq!(
+            ElfFile::read(b"\x7fELF\x01\x00"),
+            Err(ElfReadError::UnsupportedEncoding(0))
+        );
+
+        let data = build_elf(true, false, 0, &[(PT_LOAD, 0, &[1])]);
+        assert_eq!(ElfFile::read(&data[..40]), Err(ElfReadError::Truncated));
+
+        // The program header table ends past the end of the file
+        assert_eq!(ElfFile::read(&data[..100]), Err(ElfReadError::Truncated));
+
+        let data = build_elf(true, false, 0x1_0000_0000, &[]);
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::EntryPointOutOfRange(0x1_0000_0000))
+        );
+    }
+
+    #[test]
+    fn invalid_segments() {
+        let mut data = build_elf(false, false, 0, &[(PT_LOAD, 0, &[1, 2])]);
+        data.pop();
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::SegmentOutOfFile { index: 0 })
+        );
+
+        // Segments that are not loaded are not checked
+        let mut data = build_elf(false, false, 0, &[(4, 0, &[1, 2])]);
+        data.pop();
+        assert!(ElfFile::read(&data).unwrap().memory.is_empty());
+
+        let data = build_elf(
+            true,
+            false,
+            0,
+            &[(PT_LOAD, 0, &[1]), (PT_LOAD, 0xFFFF_FFFF, &[1, 2])],
+        );
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::AddressOutOfRange {
+                index: 1,
+                paddr: 0xFFFF_FFFF
+            })
+        );
+
+        let data = build_elf(true, false, 0, &[(PT_LOAD, 0x1_0000_0000, &[1])]);
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::AddressOutOfRange {
+                index: 0,
+                paddr: 0x1_0000_0000
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_program_headers() {
+        // A program header offset near the end of the 64-bit range
+        let mut data = build_elf(true, false, 0, &[(PT_LOAD, 0, &[1])]);
+        data[32..40].copy_from_slice(&0xFFFF_FFFF_FFFF_FFFEu64.to_le_bytes());
+        assert_eq!(ElfFile::read(&data), Err(ElfReadError::Truncated));
+
+        let mut data = build_elf(true, false, 0, &[(PT_LOAD, 0, &[1])]);
+        data[54..56].copy_from_slice(&8u16.to_le_bytes());
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::InvalidProgramHeaderSize(8))
+        );
+
+        let mut data = build_elf(false, true, 0, &[(PT_LOAD, 0, &[1])]);
+        data[42..44].copy_from_slice(&31u16.to_be_bytes());
+        assert_eq!(
+            ElfFile::read(&data),
+            Err(ElfReadError::InvalidProgramHeaderSize(31))
+        );
+
+        // The entry size does not matter if there are no program headers
+        let mut data = build_elf(false, false, 0, &[]);
+        data[42..44].copy_from_slice(&0u16.to_le_bytes());
+        assert!(ElfFile::read(&data).unwrap().memory.is_empty());
+    }
+}
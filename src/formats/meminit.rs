@@ -0,0 +1,430 @@
+//! Exporting the data of a file as a memory initialization file for FPGA block RAMs and ROMs.
+//!
+//! The data is flattened into a single image of fixed-width words, where word 0 holds the
+//! bytes at the base address.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::Write,
+};
+
+use crate::{
+    memory::{Endianness, MemoryMap},
+    record::file::LineEnding,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemInitFormat {
+    /// One hex word per line, as read by the Verilog `$readmemh` task.
+    #[default]
+    ReadMemH,
+    /// A Xilinx coefficient (`.coe`) file.
+    Coe,
+    /// An Altera/Intel memory initialization (`.mif`) file.
+    Mif,
+}
+
+/// Options controlling how the data of a file is written as a memory initialization file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemInitOptions {
+    pub format: MemInitFormat,
+    /// The width of a word in bytes.
+    pub word_width: u8,
+    /// The order of the bytes within a word.
+    pub endianness: Endianness,
+    /// The byte used for unmapped addresses and to pad the image up to `depth`.
+    pub fill: u8,
+    /// The address of word 0. Defaults to the lowest address in the file.
+    pub base_address: Option<u32>,
+    /// The amount of words in the memory. Defaults to the amount of words needed for the data.
+    pub depth: Option<u64>,
+    pub line_ending: LineEnding,
+}
+
+impl Default for MemInitOptions {
+    fn default() -> Self {
+        MemInitOptions {
+            format: MemInitFormat::ReadMemH,
+            word_width: 1,
+            endianness: Endianness::Little,
+            fill: 0xFF,
+            base_address: None,
+            depth: None,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// The largest image that is written, in bytes. Memory initialization files describe on-chip
+/// memories, so larger images are almost certainly a wrong base address or depth.
+pub const MAX_IMAGE_SIZE: u64 = 1 << 28;
+
+/// Flattens the data into an image of `depth` words, starting at the base address
+fn flatten(memory: &MemoryMap, options: &MemInitOptions) -> Result<Vec<u8>, MemInitError> {
+    let width = options.word_width as u64;
+
+    if width == 0 {
+        return Err(MemInitError::InvalidWordWidth);
+    }
+
+    let min = memory.min_address();
+    let base = options.base_address.or(min).unwrap_or(0);
+
+    if min.is_some_and(|min| min < base) {
+        return Err(MemInitError::BaseAboveData { base });
+    }
+
+    let len = memory
+        .max_address()
+        .map_or(0, |max| (max - base) as u64 + 1);
+    let words = len.div_ceil(width);
+    let depth = options.depth.unwrap_or(words);
+
+    if depth == 0 {
+        return Err(MemInitError::Empty);
+    }
+
+    if words > depth {
+        return Err(MemInitError::DataExceedsDepth { words, depth });
+    }
+
+    let size = depth
+        .checked_mul(width)
+        .filter(|size| *size <= MAX_IMAGE_SIZE)
+        .ok_or(MemInitError::ImageTooLarge { depth })?;
+
+    let mut data = memory.read(base, len as usize, options.fill);
+    data.resize(size as usize, options.fill);
+
+    Ok(data)
+}
+
+/// Formats a word as hex, with the most significant byte first
+fn hex_word(word: &[u8], endianness: Endianness) -> String {
+    match endianness {
+        Endianness::Little => word
+            .iter()
+            .rev()
+            .map(|byte| format!("{:02X}", byte))
+            .collect(),
+        Endianness::Big => hex::encode_upper(word),
+    }
+}
+
+fn write_readmemh<W: Write>(
+    writer: &mut W,
+    words: impl Iterator<Item = String>,
+    nl: &str,
+) -> std::io::Result<()> {
+    for word in words {
+        write!(writer, "{}{}", word, nl)?;
+    }
+
+    Ok(())
+}
+
+fn write_coe<W: Write>(
+    writer: &mut W,
+    words: impl ExactSizeIterator<Item = String>,
+    nl: &str,
+) -> std::io::Result<()> {
+    write!(writer, "memory_initialization_radix=16;{}", nl)?;
+    write!(writer, "memory_initialization_vector={}", nl)?;
+
+    let count = words.len();
+
+    for (i, word) in words.enumerate() {
+        let separator = if i + 1 == count { ';' } else { ',' };
+
+        write!(writer, "{}{}{}", word, separator, nl)?;
+    }
+
+    Ok(())
+}
+
+fn write_mif<W: Write>(
+    writer: &mut W,
+    words: impl ExactSizeIterator<Item = String>,
+    word_width: u8,
+    nl: &str,
+) -> std::io::Result<()> {
+    write!(writer, "WIDTH={};{}", word_width as u32 * 8, nl)?;
+    write!(writer, "DEPTH={};{}{}", words.len(), nl, nl)?;
+    write!(writer, "ADDRESS_RADIX=HEX;{}", nl)?;
+    write!(writer, "DATA_RADIX=HEX;{}{}", nl, nl)?;
+    write!(writer, "CONTENT BEGIN{}", nl)?;
+
+    for (i, word) in words.enumerate() {
+        write!(writer, "    {:X} : {};{}", i, word, nl)?;
+    }
+
+    write!(writer, "END;{}", nl)
+}
+
+/// Writes the flattened data of `memory` in the chosen memory initialization format.
+pub fn write_meminit<W: Write>(
+    memory: &MemoryMap,
+    writer: &mut W,
+    options: &MemInitOptions,
+) -> Result<(), MemInitError> {
+    let data = flatten(memory, options)?;
+    let words = data
+        .chunks(options.word_width as usize)
+        .map(|word| hex_word(word, options.endianness));
+    let nl = options.line_ending.as_str();
+
+    match options.format {
+        MemInitFormat::ReadMemH => write_readmemh(writer, words, nl)?,
+        MemInitFormat::Coe => write_coe(writer, words, nl)?,
+        MemInitFormat::Mif => write_mif(writer, words, options.word_width, nl)?,
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum MemInitError {
+    Io(std::io::Error),
+    InvalidWordWidth,
+    /// There is no data, and no depth was given.
+    Empty,
+    /// The memory of `depth` words is larger than [`MAX_IMAGE_SIZE`].
+    ImageTooLarge {
+        depth: u64,
+    },
+    /// The base address lies above the lowest address of the data.
+    BaseAboveData {
+        base: u32,
+    },
+    /// The data needs more words than the requested depth.
+    DataExceedsDepth {
+        words: u64,
+        depth: u64,
+    },
+}
+
+impl Display for MemInitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MemInitError::Io(e) => write!(f, "I/O error: {}", e),
+            MemInitError::InvalidWordWidth => write!(f, "Word width must be at least one byte"),
+            MemInitError::Empty => write!(f, "There is no data, and no depth was given"),
+            MemInitError::ImageTooLarge { depth } => write!(
+                f,
+                "A memory of {} words exceeds the maximum size of {} bytes",
+                depth, MAX_IMAGE_SIZE
+            ),
+            MemInitError::BaseAboveData { base } => write!(
+                f,
+                "The base address 0x{:08X} lies above the lowest address of the data",
+                base
+            ),
+            MemInitError::DataExceedsDepth { words, depth } => write!(
+                f,
+                "The data needs {} words, which does not fit in a depth of {} words",
+                words, depth
+            ),
+        }
+    }
+}
+
+impl Error for MemInitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MemInitError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MemInitError {
+    fn from(e: std::io::Error) -> Self {
+        MemInitError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> MemoryMap {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x100, &[0x11, 0x22, 0x33]);
+        memory.insert(0x104, &[0x44]);
+
+        memory
+    }
+
+    fn meminit(memory: &MemoryMap, options: &MemInitOptions) -> Result<String, MemInitError> {
+        let mut buf = Vec::new();
+        write_meminit(memory, &mut buf, options)?;
+
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn readmemh_output() {
+        let memory = example();
+
+        // Gaps are filled
+        assert_eq!(
+            meminit(&memory, &MemInitOptions::default()).unwrap(),
+            "11\n22\n33\nFF\n44\n"
+        );
+
+        let options = MemInitOptions {
+            word_width: 2,
+            fill: 0x00,
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+
+        // The last word is padded with the fill byte
+        assert_eq!(
+            meminit(&memory, &options).unwrap(),
+            "2211\r\n0033\r\n0044\r\n"
+        );
+
+        let options = MemInitOptions {
+            word_width: 4,
+            endianness: Endianness::Big,
+            ..Default::default()
+        };
+
+        assert_eq!(meminit(&memory, &options).unwrap(), "112233FF\n44FFFFFF\n");
+    }
+
+    #[test]
+    fn coe_output() {
+        let options = MemInitOptions {
+            format: MemInitFormat::Coe,
+            word_width: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            meminit(&example(), &options).unwrap(),
+            "memory_initialization_radix=16;
+memory_initialization_vector=
+2211,
+FF33,
+FF44;
+"
+        );
+    }
+
+    #[test]
+    fn mif_output() {
+        let options = MemInitOptions {
+            format: MemInitFormat::Mif,
+            word_width: 2,
+            endianness: Endianness::Big,
+            depth: Some(0x12),
+            ..Default::default()
+        };
+        let text = meminit(&example(), &options).unwrap();
+
+        assert!(text.starts_with(
+            "WIDTH=16;
+DEPTH=18;
+
+ADDRESS_RADIX=HEX;
+DATA_RADIX=HEX;
+
+CONTENT BEGIN
+    0 : 1122;
+    1 : 33FF;
+    2 : 44FF;
+    3 : FFFF;
+"
+        ));
+
+        // The addresses are hexadecimal, like the data
+        assert!(text.ends_with("    11 : FFFF;\nEND;\n"));
+    }
+
+    #[test]
+    fn base_address_and_depth() {
+        let memory = example();
+        let options = MemInitOptions {
+            base_address: Some(0xFE),
+            depth: Some(8),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            meminit(&memory, &options).unwrap(),
+            "FF\nFF\n11\n22\n33\nFF\n44\nFF\n"
+        );
+
+        let options = MemInitOptions {
+            base_address: Some(0x101),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            meminit(&memory, &options),
+            Err(MemInitError::BaseAboveData { base: 0x101 })
+        ));
+
+        let options = MemInitOptions {
+            word_width: 2,
+            depth: Some(2),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            meminit(&memory, &options),
+            Err(MemInitError::DataExceedsDepth { words: 3, depth: 2 })
+        ));
+
+        let options = MemInitOptions {
+            word_width: 0,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            meminit(&memory, &options),
+            Err(MemInitError::InvalidWordWidth)
+        ));
+    }
+
+    #[test]
+    fn image_size_limits() {
+        assert!(matches!(
+            meminit(&MemoryMap::new(), &MemInitOptions::default()),
+            Err(MemInitError::Empty)
+        ));
+
+        let options = MemInitOptions {
+            depth: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(meminit(&MemoryMap::new(), &options).unwrap(), "FF\nFF\n");
+
+        // The depth is checked before any memory is allocated for it
+        let options = MemInitOptions {
+            word_width: 4,
+            depth: Some(MAX_IMAGE_SIZE / 4 + 1),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            meminit(&example(), &options),
+            Err(MemInitError::ImageTooLarge { .. })
+        ));
+
+        let options = MemInitOptions {
+            word_width: 255,
+            depth: Some(u64::MAX),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            meminit(&example(), &options),
+            Err(MemInitError::ImageTooLarge { depth: u64::MAX })
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+//! Conversion between [`IHexFile`](crate::IHexFile) and other object file formats.
+
+use std::path::Path;
+
+pub mod elf;
+pub mod meminit;
+pub mod source;
+pub mod srec;
+pub mod titxt;
+
+/// The object file formats that can be converted to an [`IHexFile`](crate::IHexFile).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileFormat {
+    #[default]
+    IHex,
+    SRecord,
+    TiTxt,
+    /// ELF executables, which can only be read
+    Elf,
+}
+
+impl FileFormat {
+    pub const ALL: [FileFormat; 4] = [
+        FileFormat::IHex,
+        FileFormat::SRecord,
+        FileFormat::TiTxt,
+        FileFormat::Elf,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            FileFormat::IHex => "Intel HEX",
+            FileFormat::SRecord => "Motorola S-record",
+            FileFormat::TiTxt => "TI-TXT",
+            FileFormat::Elf => "ELF",
+        }
+    }
+
+    /// The file extensions commonly used for the format, without the leading dot.
+    pub const fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            FileFormat::IHex => &["hex", "ihex", "ihx"],
+            FileFormat::SRecord => &["s19", "s28", "s37", "srec", "mot"],
+            FileFormat::TiTxt => &["txt"],
+            FileFormat::Elf => &["elf", "axf"],
+        }
+    }
+
+    /// Whether an [`IHexFile`](crate::IHexFile) can be written in this format.
+    pub const fn can_write(&self) -> bool {
+        !matches!(self, FileFormat::Elf)
+    }
+
+    /// Guesses the format of a file from its extension, ignoring case.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// Guesses the format of a file from the first bytes of its contents.
+    pub fn from_contents(contents: &[u8]) -> Option<Self> {
+        if contents.starts_with(b"\x7FELF") {
+            return Some(FileFormat::Elf);
+        }
+
+        let start = contents
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())?;
+
+        match contents[start..] {
+            [b':', next, ..] if next.is_ascii_hexdigit() => Some(FileFormat::IHex),
+            [b'S', next, ..] if next.is_ascii_digit() => Some(FileFormat::SRecord),
+            [b'@', next, ..] if next.is_ascii_hexdigit() => Some(FileFormat::TiTxt),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file to read from its extension, or from the first bytes of
+    /// its contents if the extension is unknown. Plain `.txt` files are common, so they are
+    /// only read as TI-TXT if they start with an `@ADDR` line.
+    pub fn detect(path: &Path, contents: &[u8]) -> Option<Self> {
+        match Self::from_path(path) {
+            Some(FileFormat::TiTxt) | None => Self::from_contents(contents),
+            format => format,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_formats() {
+        let detect = |path: &str, contents: &[u8]| FileFormat::detect(Path::new(path), contents);
+
+        assert_eq!(detect("a.HEX", b""), Some(FileFormat::IHex));
+        assert_eq!(detect("a.s19", b":00000001FF"), Some(FileFormat::SRecord));
+        assert_eq!(detect("a.txt", b"@F000\n31 40"), Some(FileFormat::TiTxt));
+        assert_eq!(detect("a.txt", b"\r\n:00000001FF"), Some(FileFormat::IHex));
+        assert_eq!(detect("notes.txt", b"Some notes"), None);
+        assert_eq!(detect("mail.txt", b"@home"), None);
+        assert_eq!(detect("a.bin", b"\x7FELF\x01"), Some(FileFormat::Elf));
+        assert_eq!(detect("a", b"S0030000FC"), Some(FileFormat::SRecord));
+    }
+}
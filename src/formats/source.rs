@@ -0,0 +1,347 @@
+//! Exporting the data of a file as C or Rust source code, to embed it in other programs.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::Write,
+};
+
+use crate::{
+    memory::{MemoryMap, MemorySegment},
+    record::file::LineEnding,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SourceLanguage {
+    /// A C header with a `static const uint8_t` array and address and length macros per region.
+    #[default]
+    C,
+    /// A Rust module with a `static [u8; N]` and an address constant per region.
+    Rust,
+}
+
+/// Options controlling how the data of a file is written as source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceWriteOptions {
+    pub language: SourceLanguage,
+    /// The name of the generated array. When there are multiple regions, the index of the
+    /// region is appended, e.g. `image_0` and `image_1`. Rust names are uppercased.
+    pub name: String,
+    /// The amount of bytes per line. A value of 0 is treated as 1.
+    pub columns: u8,
+    pub line_ending: LineEnding,
+}
+
+impl Default for SourceWriteOptions {
+    fn default() -> Self {
+        SourceWriteOptions {
+            language: SourceLanguage::C,
+            name: "image".to_string(),
+            columns: 16,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The name of the array for the region at `index`, which only has a suffix if there
+/// are multiple regions
+fn region_name(memory: &MemoryMap, index: usize, name: &str) -> String {
+    match memory.segments().len() {
+        1 => name.to_string(),
+        _ => format!("{}_{}", name, index),
+    }
+}
+
+/// Writes the bytes of `segment` as the comma-separated body of an array literal
+fn write_bytes<W: Write>(
+    writer: &mut W,
+    segment: &MemorySegment,
+    options: &SourceWriteOptions,
+) -> std::io::Result<()> {
+    let nl = options.line_ending.as_str();
+
+    for chunk in segment.data.chunks((options.columns as usize).max(1)) {
+        let bytes: Vec<String> = chunk
+            .iter()
+            .map(|byte| format!("0x{:02X},", byte))
+            .collect();
+
+        write!(writer, "    {}{}", bytes.join(" "), nl)?;
+    }
+
+    Ok(())
+}
+
+fn write_c<W: Write>(
+    memory: &MemoryMap,
+    writer: &mut W,
+    options: &SourceWriteOptions,
+) -> std::io::Result<()> {
+    let nl = options.line_ending.as_str();
+    let guard = format!("{}_H", options.name.to_ascii_uppercase());
+
+    write!(
+        writer,
+        "#ifndef {}{}#define {}{}{}",
+        guard, nl, guard, nl, nl
+    )?;
+    write!(writer, "#include <stdint.h>{}", nl)?;
+
+    for (i, segment) in memory.segments().iter().enumerate() {
+        let name = region_name(memory, i, &options.name);
+        let macro_name = name.to_ascii_uppercase();
+
+        write!(writer, "{}", nl)?;
+        write!(
+            writer,
+            "#define {}_ADDRESS 0x{:08X}u{}",
+            macro_name, segment.start, nl
+        )?;
+        write!(
+            writer,
+            "#define {}_LENGTH {}u{}{}",
+            macro_name,
+            segment.data.len(),
+            nl,
+            nl
+        )?;
+        write!(
+            writer,
+            "static const uint8_t {}[{}_LENGTH] = {{{}",
+            name, macro_name, nl
+        )?;
+        write_bytes(writer, segment, options)?;
+        write!(writer, "}};{}", nl)?;
+    }
+
+    write!(writer, "{}#endif /* {} */{}", nl, guard, nl)
+}
+
+fn write_rust<W: Write>(
+    memory: &MemoryMap,
+    writer: &mut W,
+    options: &SourceWriteOptions,
+) -> std::io::Result<()> {
+    let nl = options.line_ending.as_str();
+
+    for (i, segment) in memory.segments().iter().enumerate() {
+        let name = region_name(memory, i, &options.name).to_ascii_uppercase();
+
+        if i > 0 {
+            write!(writer, "{}", nl)?;
+        }
+
+        write!(
+            writer,
+            "pub const {}_ADDRESS: u32 = 0x{:08X};{}",
+            name, segment.start, nl
+        )?;
+        write!(
+            writer,
+            "pub static {}: [u8; {}] = [{}",
+            name,
+            segment.data.len(),
+            nl
+        )?;
+        write_bytes(writer, segment, options)?;
+        write!(writer, "];{}", nl)?;
+    }
+
+    Ok(())
+}
+
+/// Writes every contiguous region of `memory` as an array in the chosen language.
+pub fn write_source<W: Write>(
+    memory: &MemoryMap,
+    writer: &mut W,
+    options: &SourceWriteOptions,
+) -> Result<(), SourceExportError> {
+    if !is_identifier(&options.name) {
+        return Err(SourceExportError::InvalidName(options.name.clone()));
+    }
+
+    match options.language {
+        SourceLanguage::C => write_c(memory, writer, options)?,
+        SourceLanguage::Rust => write_rust(memory, writer, options)?,
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum SourceExportError {
+    Io(std::io::Error),
+    /// The symbol name is not a valid C or Rust identifier.
+    InvalidName(String),
+}
+
+impl Display for SourceExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceExportError::Io(e) => write!(f, "I/O error: {}", e),
+            SourceExportError::InvalidName(name) => write!(
+                f,
+                "\"{}\" is not a valid symbol name. Use only letters, digits and underscores, and do not start with a digit",
+                name
+            ),
+        }
+    }
+}
+
+impl Error for SourceExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SourceExportError::Io(e) => Some(e),
+            SourceExportError::InvalidName(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SourceExportError {
+    fn from(e: std::io::Error) -> Self {
+        SourceExportError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(segments: &[(u32, &[u8])]) -> MemoryMap {
+        let mut memory = MemoryMap::new();
+
+        for (start, data) in segments {
+            memory.insert(*start, data);
+        }
+
+        memory
+    }
+
+    fn source(memory: &MemoryMap, options: &SourceWriteOptions) -> String {
+        let mut buf = Vec::new();
+        write_source(memory, &mut buf, options).unwrap();
+
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn c_output() {
+        let memory = memory(&[(0x0800_0000, &[0x01, 0x02, 0xAB])]);
+        let options = SourceWriteOptions {
+            columns: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            source(&memory, &options),
+            "#ifndef IMAGE_H
+#define IMAGE_H
+
+#include <stdint.h>
+
+#define IMAGE_ADDRESS 0x08000000u
+#define IMAGE_LENGTH 3u
+
+static const uint8_t image[IMAGE_LENGTH] = {
+    0x01, 0x02,
+    0xAB,
+};
+
+#endif /* IMAGE_H */
+"
+        );
+    }
+
+    #[test]
+    fn c_output_with_regions() {
+        let memory = memory(&[(0x00, &[0x01]), (0x10, &[0x02])]);
+        let options = SourceWriteOptions {
+            name: "fw".to_string(),
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let text = source(&memory, &options);
+
+        assert!(text.starts_with("#ifndef FW_H\r\n"));
+        assert!(text.contains("#define FW_0_ADDRESS 0x00000000u\r\n"));
+        assert!(text.contains("static const uint8_t fw_1[FW_1_LENGTH] = {\r\n    0x02,\r\n};\r\n"));
+        assert!(text.ends_with("#endif /* FW_H */\r\n"));
+        assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+    }
+
+    #[test]
+    fn rust_output() {
+        let regions = memory(&[(0x00, &[0xAB]), (0x100, &[0xCD, 0xEF])]);
+        let options = SourceWriteOptions {
+            language: SourceLanguage::Rust,
+            name: "fw".to_string(),
+            columns: 0,
+            ..Default::default()
+        };
+
+        // A column count of 0 puts every byte on its own line
+        assert_eq!(
+            source(&regions, &options),
+            "pub const FW_0_ADDRESS: u32 = 0x00000000;
+pub static FW_0: [u8; 1] = [
+    0xAB,
+];
+
+pub const FW_1_ADDRESS: u32 = 0x00000100;
+pub static FW_1: [u8; 2] = [
+    0xCD,
+    0xEF,
+];
+"
+        );
+
+        let last_byte = memory(&[(0xFFFF_FFFF, &[0x00])]);
+        let options = SourceWriteOptions {
+            language: SourceLanguage::Rust,
+            ..Default::default()
+        };
+
+        assert!(source(&last_byte, &options).starts_with(
+            "pub const IMAGE_ADDRESS: u32 = 0xFFFFFFFF;\npub static IMAGE: [u8; 1] = [\n"
+        ));
+    }
+
+    #[test]
+    fn symbol_names() {
+        let memory = memory(&[(0, &[0])]);
+
+        for name in ["", "1image", "my-image", "image.bin", "ïmage"] {
+            let options = SourceWriteOptions {
+                name: name.to_string(),
+                ..Default::default()
+            };
+
+            assert!(
+                matches!(
+                    write_source(&memory, &mut Vec::new(), &options),
+                    Err(SourceExportError::InvalidName(invalid)) if invalid == name
+                ),
+                "{}",
+                name
+            );
+        }
+
+        for name in ["_image", "image_2", "Image"] {
+            let options = SourceWriteOptions {
+                name: name.to_string(),
+                ..Default::default()
+            };
+
+            assert!(write_source(&memory, &mut Vec::new(), &options).is_ok());
+        }
+    }
+}
@@ -0,0 +1,776 @@
+//! Motorola S-record files (S19, S28 and S37).
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{BufRead, Lines, Write},
+};
+
+use crate::{
+    memory::MemoryMap,
+    record::{
+        binary::{BinaryImportError, BinaryImportOptions},
+        file::{IHexFile, LineEnding},
+    },
+};
+
+/// The width of the addresses in the data and termination records of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SRecordAddressWidth {
+    /// S1 data records and an S9 termination record.
+    Bits16,
+    /// S2 data records and an S8 termination record.
+    Bits24,
+    /// S3 data records and an S7 termination record.
+    Bits32,
+}
+
+impl SRecordAddressWidth {
+    /// The narrowest address width that can represent `address`.
+    pub const fn for_address(address: u32) -> Self {
+        if address <= 0xFFFF {
+            SRecordAddressWidth::Bits16
+        } else if address <= 0xFF_FFFF {
+            SRecordAddressWidth::Bits24
+        } else {
+            SRecordAddressWidth::Bits32
+        }
+    }
+
+    const fn address_len(&self) -> usize {
+        match self {
+            SRecordAddressWidth::Bits16 => 2,
+            SRecordAddressWidth::Bits24 => 3,
+            SRecordAddressWidth::Bits32 => 4,
+        }
+    }
+
+    const fn data_type(&self) -> u8 {
+        match self {
+            SRecordAddressWidth::Bits16 => 1,
+            SRecordAddressWidth::Bits24 => 2,
+            SRecordAddressWidth::Bits32 => 3,
+        }
+    }
+
+    const fn termination_type(&self) -> u8 {
+        match self {
+            SRecordAddressWidth::Bits16 => 9,
+            SRecordAddressWidth::Bits24 => 8,
+            SRecordAddressWidth::Bits32 => 7,
+        }
+    }
+}
+
+/// Options controlling how an [`SRecordFile`] is serialized to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SRecordWriteOptions {
+    /// The maximum amount of data bytes per data record. Values that do not fit in a
+    /// record of the chosen address width are clamped.
+    pub record_size: u8,
+    pub line_ending: LineEnding,
+}
+
+impl Default for SRecordWriteOptions {
+    fn default() -> Self {
+        SRecordWriteOptions {
+            record_size: 16,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// A single S-record, with its checksum already verified
+struct RawSRecord {
+    rectype: u8,
+    address: u32,
+    data: Vec<u8>,
+}
+
+/// The one's complement of the sum of the byte count, address and data bytes
+fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn parse_srecord(line: &str) -> Result<RawSRecord, SRecordReadErrorKind> {
+    if !line.is_ascii() {
+        return Err(SRecordReadErrorKind::NonAsciiString);
+    }
+
+    if !line.starts_with('S') {
+        return Err(SRecordReadErrorKind::MissingStartCode);
+    }
+
+    let rectype = match line.as_bytes().get(1) {
+        Some(c @ b'0'..=b'9') if *c != b'4' => c - b'0',
+        Some(c) => return Err(SRecordReadErrorKind::InvalidRecordType(*c as char)),
+        None => return Err(SRecordReadErrorKind::RecordTooShort),
+    };
+
+    let bytes = hex::decode(&line[2..]).map_err(|_| SRecordReadErrorKind::NonHexString)?;
+
+    let (&count, rest) = bytes
+        .split_first()
+        .ok_or(SRecordReadErrorKind::RecordTooShort)?;
+
+    if rest.len() != count as usize {
+        return Err(SRecordReadErrorKind::IncorrectDataSize);
+    }
+
+    let (&found, rest) = rest
+        .split_last()
+        .ok_or(SRecordReadErrorKind::RecordTooShort)?;
+    let expected = checksum(&bytes[..bytes.len() - 1]);
+
+    if found != expected {
+        return Err(SRecordReadErrorKind::Checksum { expected, found });
+    }
+
+    let address_len = match rectype {
+        0 | 1 | 5 | 9 => 2,
+        2 | 6 | 8 => 3,
+        _ => 4,
+    };
+
+    if rest.len() < address_len {
+        return Err(SRecordReadErrorKind::RecordTooShort);
+    }
+
+    let (address, data) = rest.split_at(address_len);
+
+    Ok(RawSRecord {
+        rectype,
+        address: address
+            .iter()
+            .fold(0, |address, byte| (address << 8) | *byte as u32),
+        data: data.to_vec(),
+    })
+}
+
+fn write_record<W: Write>(
+    writer: &mut W,
+    rectype: u8,
+    address: u32,
+    address_len: usize,
+    data: &[u8],
+    line_ending: LineEnding,
+) -> std::io::Result<()> {
+    let mut bytes = Vec::with_capacity(address_len + data.len() + 2);
+
+    bytes.push((address_len + data.len() + 1) as u8);
+    bytes.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+    bytes.extend_from_slice(data);
+    bytes.push(checksum(&bytes));
+
+    write!(
+        writer,
+        "S{}{}{}",
+        rectype,
+        hex::encode_upper(&bytes),
+        line_ending.as_str()
+    )
+}
+
+/// The contents of an S-record file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SRecordFile {
+    /// The data of the S0 header record, usually a module name.
+    pub header: Vec<u8>,
+    pub memory: MemoryMap,
+    /// The address in the termination record. Files are always written with a termination
+    /// record, which gets address 0 if there is no start address.
+    pub start_address: Option<u32>,
+}
+
+impl SRecordFile {
+    /// Reads a file, aborting on the first invalid record. Empty lines are skipped.
+    pub fn read<T: BufRead>(lines: Lines<T>) -> Result<Self, SRecordReadError> {
+        let (file, mut errors) = Self::read_impl(lines, false);
+
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(file),
+        }
+    }
+
+    /// Reads a file, skipping invalid records instead of aborting.
+    /// Returns the file containing all valid records, along with an error for each
+    /// record that could not be read.
+    pub fn read_lenient<T: BufRead>(lines: Lines<T>) -> (Self, Vec<SRecordReadError>) {
+        Self::read_impl(lines, true)
+    }
+
+    fn read_impl<T: BufRead>(lines: Lines<T>, lenient: bool) -> (Self, Vec<SRecordReadError>) {
+        let mut file = SRecordFile::default();
+        let mut errors = Vec::new();
+        let mut data_records = 0;
+
+        for (i, line) in lines.enumerate() {
+            let line_number = i + 1;
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    errors.push(SRecordReadError::new(
+                        line_number,
+                        "",
+                        SRecordReadErrorKind::Io(e),
+                    ));
+                    break;
+                }
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let result = parse_srecord(&line)
+                .and_then(|record| file.apply_record(record, &mut data_records));
+
+            if let Err(kind) = result {
+                errors.push(SRecordReadError::new(line_number, &line, kind));
+
+                if !lenient {
+                    break;
+                }
+            }
+        }
+
+        (file, errors)
+    }
+
+    fn apply_record(
+        &mut self,
+        record: RawSRecord,
+        data_records: &mut u32,
+    ) -> Result<(), SRecordReadErrorKind> {
+        match record.rectype {
+            0 => self.header = record.data,
+            1..=3 => {
+                if record.address as u64 + record.data.len() as u64 > 1 << 32 {
+                    return Err(SRecordReadErrorKind::AddressOutOfRange);
+                }
+
+                self.memory.insert(record.address, &record.data);
+                *data_records += 1;
+            }
+            5 | 6 => {
+                if record.address != *data_records {
+                    return Err(SRecordReadErrorKind::RecordCount {
+                        expected: record.address,
+                        found: *data_records,
+                    });
+                }
+            }
+            _ => self.start_address = Some(record.address),
+        }
+
+        Ok(())
+    }
+
+    /// The narrowest address width that can represent all data and the start address.
+    pub fn address_width(&self) -> SRecordAddressWidth {
+        let max_data = self.memory.max_address().unwrap_or(0);
+        let max_address = max_data.max(self.start_address.unwrap_or(0));
+
+        SRecordAddressWidth::for_address(max_address)
+    }
+
+    /// Serializes the file as a header record, the data records, a record count
+    /// and a termination record. The record types are chosen by [`Self::address_width`].
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &SRecordWriteOptions,
+    ) -> std::io::Result<()> {
+        let width = self.address_width();
+        let address_len = width.address_len();
+        let line_ending = options.line_ending;
+
+        // The byte count covers the address, data and checksum, and is at most 255
+        let max_record_size = 255 - address_len - 1;
+        let record_size = (options.record_size as usize).clamp(1, max_record_size);
+        let header = &self.header[..self.header.len().min(255 - 2 - 1)];
+
+        write_record(writer, 0, 0, 2, header, line_ending)?;
+
+        let mut data_records: u32 = 0;
+
+        for segment in self.memory.segments() {
+            for (i, chunk) in segment.data.chunks(record_size).enumerate() {
+                let address = segment.start + (i * record_size) as u32;
+
+                write_record(
+                    writer,
+                    width.data_type(),
+                    address,
+                    address_len,
+                    chunk,
+                    line_ending,
+                )?;
+                data_records += 1;
+            }
+        }
+
+        if data_records <= 0xFFFF {
+            write_record(writer, 5, data_records, 2, &[], line_ending)?;
+        } else if data_records <= 0xFF_FFFF {
+            write_record(writer, 6, data_records, 3, &[], line_ending)?;
+        }
+
+        write_record(
+            writer,
+            width.termination_type(),
+            self.start_address.unwrap_or(0),
+            address_len,
+            &[],
+            line_ending,
+        )
+    }
+
+    pub fn to_srec_string(&self, options: &SRecordWriteOptions) -> String {
+        let mut buf = Vec::new();
+
+        self.write(&mut buf, options)
+            .expect("Writing to a Vec should never fail");
+
+        String::from_utf8(buf).expect("S-record output is always ASCII")
+    }
+
+    /// Takes the data and start address of an Intel HEX file. Segment start
+    /// addresses are converted to linear addresses.
+    pub fn from_ihex(file: &IHexFile) -> Self {
+        SRecordFile {
+            header: Vec::new(),
+            memory: file.data_bytes(),
+            start_address: file.start_address().map(|start| start.linear_address()),
+        }
+    }
+
+    /// Generates an Intel HEX file with the same data and start address.
+    /// `options.start_address` is only used if this file has no start address.
+    pub fn to_ihex(&self, options: &BinaryImportOptions) -> Result<IHexFile, BinaryImportError> {
+        let start_address = match self.start_address {
+            Some(address) => Some(options.addressing.start_address(address)?),
+            None => options.start_address,
+        };
+
+        IHexFile::from_memory_map(
+            &self.memory,
+            &BinaryImportOptions {
+                start_address,
+                ..*options
+            },
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum SRecordReadErrorKind {
+    Io(std::io::Error),
+    MissingStartCode,
+    NonAsciiString,
+    InvalidRecordType(char),
+    NonHexString,
+    RecordTooShort,
+    IncorrectDataSize,
+    Checksum { expected: u8, found: u8 },
+    AddressOutOfRange,
+    RecordCount { expected: u32, found: u32 },
+}
+
+impl Display for SRecordReadErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SRecordReadErrorKind::Io(e) => write!(f, "I/O error: {}", e),
+            SRecordReadErrorKind::MissingStartCode => write!(f, "Record is missing start code"),
+            SRecordReadErrorKind::NonAsciiString => {
+                write!(f, "Record contains non-ASCII characters")
+            }
+            SRecordReadErrorKind::InvalidRecordType(c) => {
+                write!(f, "Invalid record type 'S{}'", c)
+            }
+            SRecordReadErrorKind::NonHexString => write!(f, "Record contains non-hex characters"),
+            SRecordReadErrorKind::RecordTooShort => {
+                write!(f, "Record is too short for its record type")
+            }
+            SRecordReadErrorKind::IncorrectDataSize => {
+                write!(f, "Record length does not match byte count in record")
+            }
+            SRecordReadErrorKind::Checksum { expected, found } => write!(
+                f,
+                "Invalid checksum: expected 0x{:02X}, found 0x{:02X}",
+                expected, found
+            ),
+            SRecordReadErrorKind::AddressOutOfRange => {
+                write!(f, "Data extends past the end of the 32-bit address space")
+            }
+            SRecordReadErrorKind::RecordCount { expected, found } => write!(
+                f,
+                "Record count mismatch: the count record expects {} data records, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+/// An error encountered while reading an S-record file, along with its location.
+#[derive(Debug)]
+pub struct SRecordReadError {
+    /// The 1-based line number of the offending record.
+    pub line: usize,
+    /// The 1-based column at which the error was detected, if known.
+    pub column: Option<usize>,
+    /// The text of the offending record.
+    pub record: String,
+    pub kind: SRecordReadErrorKind,
+}
+
+impl SRecordReadError {
+    fn new(line: usize, record: &str, kind: SRecordReadErrorKind) -> Self {
+        SRecordReadError {
+            line,
+            column: Self::error_column(record, &kind),
+            record: record.to_string(),
+            kind,
+        }
+    }
+
+    fn error_column(record: &str, kind: &SRecordReadErrorKind) -> Option<usize> {
+        let column = match kind {
+            SRecordReadErrorKind::Io(_) => return None,
+            SRecordReadErrorKind::MissingStartCode => 1,
+            SRecordReadErrorKind::NonAsciiString => record.chars().position(|c| !c.is_ascii())? + 1,
+            SRecordReadErrorKind::InvalidRecordType(_) => 2,
+            SRecordReadErrorKind::NonHexString => {
+                match record[2..].chars().position(|c| !c.is_ascii_hexdigit()) {
+                    Some(pos) => pos + 3,
+                    None => record.len(), // Odd amount of hex digits
+                }
+            }
+            SRecordReadErrorKind::RecordTooShort => record.len() + 1,
+            SRecordReadErrorKind::IncorrectDataSize => 3,
+            SRecordReadErrorKind::Checksum { .. } => record.len() - 1,
+            SRecordReadErrorKind::AddressOutOfRange | SRecordReadErrorKind::RecordCount { .. } => 5,
+        };
+
+        Some(column)
+    }
+}
+
+impl Display for SRecordReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "Line {}, column {}: {}", self.line, column, self.kind)?,
+            None => write!(f, "Line {}: {}", self.line, self.kind)?,
+        }
+
+        if !self.record.is_empty() {
+            write!(f, " (record: \"{}\")", self.record)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for SRecordReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            SRecordReadErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+    use crate::record::{
+        binary::AddressingMode,
+        file::{SegmentStartAddr, StartAddr},
+    };
+
+    /// The example file from the S-record article on Wikipedia
+    const EXAMPLE: &str = "S00F000068656C6C6F202020202000003C
+S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026
+S11F001C4BFFFFE5398000007D83637880010014382100107C0803A64E800020E9
+S111003848656C6C6F20776F726C642E0A0042
+S5030003F9
+S9030000FC
+";
+
+    fn read(text: &str) -> SRecordFile {
+        SRecordFile::read(text.as_bytes().lines()).unwrap()
+    }
+
+    fn read_error(text: &str) -> SRecordReadError {
+        SRecordFile::read(text.as_bytes().lines()).unwrap_err()
+    }
+
+    fn file(address: u32, data: &[u8], start_address: Option<u32>) -> SRecordFile {
+        let mut file = SRecordFile {
+            start_address,
+            ..Default::default()
+        };
+        file.memory.insert(address, data);
+
+        file
+    }
+
+    /// The record type of every line, e.g. `S0 S1 S5 S9`
+    fn record_types(text: &str) -> String {
+        text.lines()
+            .map(|line| &line[..2])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn read_example() {
+        let file = read(EXAMPLE);
+
+        assert_eq!(file.header, b"hello     \0\0");
+        assert_eq!(file.memory.segments().len(), 1);
+        assert_eq!(file.memory.min_address(), Some(0));
+        assert_eq!(file.memory.len(), 0x46);
+        assert_eq!(file.memory.read(0x38, 5, 0), b"Hello");
+
+        // Empty lines are skipped
+        let text = format!("\n{}\n", EXAMPLE.replace('\n', "\n\n"));
+        assert_eq!(read(&text), file);
+    }
+
+    #[test]
+    fn address_width_selection() {
+        let options = SRecordWriteOptions::default();
+
+        assert_eq!(
+            file(0x1234, &[0xAB], None).to_srec_string(&options),
+            "S0030000FC\nS1041234AB0A\nS5030001FB\nS9030000FC\n"
+        );
+
+        let text = file(0xFFFF, &[0xAB], None).to_srec_string(&options);
+        assert_eq!(record_types(&text), "S0 S1 S5 S9");
+
+        // The last byte decides the width, not the start of the data
+        let text = file(0xFFFF, &[0xAB, 0xCD], None).to_srec_string(&options);
+        assert_eq!(record_types(&text), "S0 S2 S5 S8");
+
+        let text = file(0x0100_0000, &[0xAB], None).to_srec_string(&options);
+        assert_eq!(record_types(&text), "S0 S3 S5 S7");
+
+        // A start address above the data widens every record
+        let text = file(0, &[0xAB], Some(0x12_3456)).to_srec_string(&options);
+        assert_eq!(record_types(&text), "S0 S2 S5 S8");
+        assert!(text.ends_with("\nS8041234565F\n"));
+    }
+
+    #[test]
+    fn record_sizes() {
+        let mut file = file(0x0100_0000, &[0x55; 600], None);
+        file.header = vec![b'h'; 300];
+
+        let options = SRecordWriteOptions {
+            record_size: 255,
+            ..Default::default()
+        };
+        let text = file.to_srec_string(&options);
+        let lines: Vec<_> = text.lines().collect();
+
+        // The byte count includes the 4 address bytes and the checksum, and the
+        // header is cut off at the maximum record length
+        assert!(lines[0].starts_with("S0FF0000"));
+        assert!(lines[1].starts_with("S3FF01000000"));
+        assert!(lines[2].starts_with("S3FF010000FA"));
+        assert!(lines[3].starts_with("S369010001F4"));
+        assert_eq!(read(&text).header.len(), 252);
+        assert_eq!(read(&text).memory, file.memory);
+
+        let options = SRecordWriteOptions {
+            record_size: 0,
+            ..Default::default()
+        };
+        let text = file.to_srec_string(&options);
+
+        assert_eq!(
+            text.lines().filter(|line| line.starts_with("S3")).count(),
+            600
+        );
+    }
+
+    #[test]
+    fn count_records() {
+        let data = vec![0xAA; 0x1_0000];
+        let options = SRecordWriteOptions {
+            record_size: 1,
+            ..Default::default()
+        };
+
+        let text = file(0, &data[1..], None).to_srec_string(&options);
+        assert!(text.ends_with("\nS503FFFFFE\nS9030000FC\n"));
+
+        // Counts above 0xFFFF need the 24-bit count record
+        let text = file(0, &data, None).to_srec_string(&options);
+        assert!(text.ends_with("\nS604010000FA\nS9030000FC\n"));
+        assert_eq!(read(&text).memory.len(), 0x1_0000);
+
+        let error = read_error("S10500000102F7\nS5030002FA\n");
+        assert_eq!((error.line, error.column), (2, Some(5)));
+        assert!(matches!(
+            error.kind,
+            SRecordReadErrorKind::RecordCount {
+                expected: 2,
+                found: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn malformed_records() {
+        let check = |record: &str, column: usize| {
+            let error = read_error(&format!("S10500000102F7\n{}\n", record));
+
+            assert_eq!((error.line, error.column), (2, Some(column)), "{}", record);
+            assert_eq!(error.record, record);
+
+            error.kind
+        };
+
+        assert!(matches!(
+            check("X10500000102F7", 1),
+            SRecordReadErrorKind::MissingStartCode
+        ));
+        assert!(matches!(
+            check("S40500000102F7", 2),
+            SRecordReadErrorKind::InvalidRecordType('4')
+        ));
+        assert!(matches!(
+            check("S", 2),
+            SRecordReadErrorKind::RecordTooShort
+        ));
+        assert!(matches!(
+            check("S1050000010ZF7", 12),
+            SRecordReadErrorKind::NonHexString
+        ));
+        assert!(matches!(
+            check("S10600000102F7", 3),
+            SRecordReadErrorKind::IncorrectDataSize
+        ));
+        assert!(matches!(
+            check("S10500000102F8", 13),
+            SRecordReadErrorKind::Checksum {
+                expected: 0xF7,
+                found: 0xF8
+            }
+        ));
+
+        // The byte count only leaves room for one of the two address bytes
+        assert!(matches!(
+            check("S10200FD", 9),
+            SRecordReadErrorKind::RecordTooShort
+        ));
+        assert!(matches!(
+            check("S307FFFFFFFF0102F9", 5),
+            SRecordReadErrorKind::AddressOutOfRange
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut file = read(EXAMPLE);
+        file.memory.insert(0x0123_4560, &[0xDE, 0xAD, 0xBE, 0xEF]);
+        file.start_address = Some(0x0123_4560);
+
+        let options = SRecordWriteOptions {
+            record_size: 7,
+            line_ending: LineEnding::CrLf,
+        };
+        let text = file.to_srec_string(&options);
+
+        assert_eq!(text.matches("\r\n").count(), text.matches('\n').count());
+        assert_eq!(record_types(&text).matches("S3").count(), 11);
+        assert!(text.ends_with("\r\nS7050123456031\r\n"));
+        assert_eq!(read(&text), file);
+    }
+
+    #[test]
+    fn invalid_records_lenient() {
+        let text = EXAMPLE.replace("S111003848", "S111003849");
+
+        let error = read_error(&text);
+        assert_eq!(error.line, 4);
+        assert!(matches!(error.kind, SRecordReadErrorKind::Checksum { .. }));
+
+        // The count record no longer matches after the broken record is skipped
+        let (file, errors) = SRecordFile::read_lenient(text.as_bytes().lines());
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            errors[1].kind,
+            SRecordReadErrorKind::RecordCount {
+                expected: 3,
+                found: 2
+            }
+        ));
+        assert_eq!(file.memory.len(), 0x38);
+    }
+
+    #[test]
+    fn ihex_start_addresses() {
+        let srec = file(0x100, &[1, 2, 3], Some(0x1_2345));
+
+        let ihex = srec.to_ihex(&BinaryImportOptions::default()).unwrap();
+        assert!(matches!(
+            ihex.start_address(),
+            Some(StartAddr::Linear(0x1_2345))
+        ));
+        assert_eq!(SRecordFile::from_ihex(&ihex), srec);
+
+        let segment = BinaryImportOptions {
+            addressing: AddressingMode::Segment,
+            ..Default::default()
+        };
+        let ihex = srec.to_ihex(&segment).unwrap();
+        assert!(matches!(
+            ihex.start_address(),
+            Some(StartAddr::Segment(SegmentStartAddr {
+                code_segment: 0x1234,
+                instruction_pointer: 0x5,
+            }))
+        ));
+        assert_eq!(SRecordFile::from_ihex(&ihex).start_address, Some(0x1_2345));
+
+        assert!(matches!(
+            file(0x100, &[1], Some(0x10_0000)).to_ihex(&segment),
+            Err(BinaryImportError::AddressOutOfRange)
+        ));
+
+        // The start address of the options is only used if the file has none
+        let options = BinaryImportOptions {
+            start_address: Some(StartAddr::Linear(0x100)),
+            ..Default::default()
+        };
+        let ihex = file(0x100, &[1], None).to_ihex(&options).unwrap();
+        assert!(matches!(
+            ihex.start_address(),
+            Some(StartAddr::Linear(0x100))
+        ));
+    }
+
+    #[test]
+    fn start_address_zero() {
+        assert_eq!(read(EXAMPLE).start_address, Some(0));
+
+        let file = file(0x100, &[1], Some(0));
+        let text = file.to_srec_string(&SRecordWriteOptions::default());
+
+        assert_eq!(read(&text).start_address, Some(0));
+
+        let ihex = file.to_ihex(&BinaryImportOptions::default()).unwrap();
+        assert_eq!(SRecordFile::from_ihex(&ihex).start_address, Some(0));
+
+        // Without a termination record, there is no start address
+        assert_eq!(read("S10500000102F7\n").start_address, None);
+    }
+}
@@ -0,0 +1,462 @@
+//! TI-TXT files, as used to program MSP430 devices.
+//!
+//! A file consists of sections that start with an `@ADDR` line, followed by lines of
+//! space-separated hex bytes, and ends with a `q` line.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{BufRead, Lines, Write},
+};
+
+use crate::{
+    memory::MemoryMap,
+    record::{
+        binary::{BinaryImportError, BinaryImportOptions},
+        file::{IHexFile, LineEnding},
+    },
+};
+
+/// Options controlling how a [`TiTxtFile`] is serialized to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiTxtWriteOptions {
+    /// The maximum amount of bytes per line. A value of 0 is treated as 1.
+    pub line_length: u8,
+    pub line_ending: LineEnding,
+}
+
+impl Default for TiTxtWriteOptions {
+    fn default() -> Self {
+        TiTxtWriteOptions {
+            line_length: 16,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// The contents of a TI-TXT file. The format has no start address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TiTxtFile {
+    pub memory: MemoryMap,
+}
+
+/// The byte offset of `token` within `line`, which it must be a slice of
+fn token_column(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+impl TiTxtFile {
+    /// Reads a file, aborting on the first invalid line. Reading stops at the `q` line.
+    pub fn read<T: BufRead>(lines: Lines<T>) -> Result<Self, TiTxtReadError> {
+        let (file, mut errors) = Self::read_impl(lines, false);
+
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(file),
+        }
+    }
+
+    /// Reads a file, skipping invalid lines instead of aborting.
+    /// Returns the file containing all valid lines, along with an error for each
+    /// line that could not be read.
+    pub fn read_lenient<T: BufRead>(lines: Lines<T>) -> (Self, Vec<TiTxtReadError>) {
+        Self::read_impl(lines, true)
+    }
+
+    fn read_impl<T: BufRead>(lines: Lines<T>, lenient: bool) -> (Self, Vec<TiTxtReadError>) {
+        let mut file = TiTxtFile::default();
+        let mut errors = Vec::new();
+        let mut address: Option<u64> = None;
+
+        for (i, line) in lines.enumerate() {
+            let line_number = i + 1;
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    errors.push(TiTxtReadError {
+                        line: line_number,
+                        column: None,
+                        text: String::new(),
+                        kind: TiTxtReadErrorKind::Io(e),
+                    });
+                    break;
+                }
+            };
+
+            let trimmed = line.trim();
+
+            if trimmed.eq_ignore_ascii_case("q") {
+                break;
+            }
+
+            if let Err((column, kind)) = file.parse_line(&line, &mut address) {
+                errors.push(TiTxtReadError {
+                    line: line_number,
+                    column: Some(column),
+                    text: line.clone(),
+                    kind,
+                });
+
+                if !lenient {
+                    break;
+                }
+            }
+        }
+
+        (file, errors)
+    }
+
+    /// Parses a section header or a line of data bytes, which are stored at `address`
+    fn parse_line(
+        &mut self,
+        line: &str,
+        address: &mut Option<u64>,
+    ) -> Result<(), (usize, TiTxtReadErrorKind)> {
+        let trimmed = line.trim();
+
+        if let Some(digits) = trimmed.strip_prefix('@') {
+            let parsed = u32::from_str_radix(digits, 16).map_err(|_| {
+                (
+                    token_column(line, trimmed),
+                    TiTxtReadErrorKind::InvalidAddress,
+                )
+            })?;
+
+            *address = Some(parsed as u64);
+            return Ok(());
+        }
+
+        let mut data = Vec::new();
+
+        for token in trimmed.split_ascii_whitespace() {
+            let byte = match token.len() {
+                2 => u8::from_str_radix(token, 16).ok(),
+                _ => None,
+            };
+
+            match byte {
+                Some(byte) => data.push(byte),
+                None => {
+                    return Err((
+                        token_column(line, token),
+                        TiTxtReadErrorKind::InvalidByte(token.to_string()),
+                    ))
+                }
+            }
+        }
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let Some(start) = *address else {
+            return Err((
+                token_column(line, trimmed),
+                TiTxtReadErrorKind::MissingAddress,
+            ));
+        };
+
+        let end = start + data.len() as u64;
+
+        if end > 1 << 32 {
+            return Err((
+                token_column(line, trimmed),
+                TiTxtReadErrorKind::AddressOutOfRange,
+            ));
+        }
+
+        self.memory.insert(start as u32, &data);
+        *address = Some(end);
+
+        Ok(())
+    }
+
+    /// Serializes the file as one section per contiguous region, followed by a `q` line.
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &TiTxtWriteOptions,
+    ) -> std::io::Result<()> {
+        let line_ending = options.line_ending.as_str();
+        let line_length = (options.line_length as usize).max(1);
+
+        for segment in self.memory.segments() {
+            write!(writer, "@{:04X}{}", segment.start, line_ending)?;
+
+            for chunk in segment.data.chunks(line_length) {
+                let bytes: Vec<String> = chunk.iter().map(|byte| format!("{:02X}", byte)).collect();
+
+                write!(writer, "{}{}", bytes.join(" "), line_ending)?;
+            }
+        }
+
+        write!(writer, "q{}", line_ending)
+    }
+
+    pub fn to_titxt_string(&self, options: &TiTxtWriteOptions) -> String {
+        let mut buf = Vec::new();
+
+        self.write(&mut buf, options)
+            .expect("Writing to a Vec should never fail");
+
+        String::from_utf8(buf).expect("TI-TXT output is always ASCII")
+    }
+
+    /// Takes the data of an Intel HEX file. The start address is discarded.
+    pub fn from_ihex(file: &IHexFile) -> Self {
+        TiTxtFile {
+            memory: file.data_bytes(),
+        }
+    }
+
+    /// Generates an Intel HEX file with the same data, and the start address from `options`.
+    pub fn to_ihex(&self, options: &BinaryImportOptions) -> Result<IHexFile, BinaryImportError> {
+        IHexFile::from_memory_map(&self.memory, options)
+    }
+}
+
+#[derive(Debug)]
+pub enum TiTxtReadErrorKind {
+    Io(std::io::Error),
+    InvalidAddress,
+    InvalidByte(String),
+    MissingAddress,
+    AddressOutOfRange,
+}
+
+impl Display for TiTxtReadErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TiTxtReadErrorKind::Io(e) => write!(f, "I/O error: {}", e),
+            TiTxtReadErrorKind::InvalidAddress => {
+                write!(f, "Section address is not a 32-bit hex number")
+            }
+            TiTxtReadErrorKind::InvalidByte(token) => {
+                write!(f, "Invalid hex byte \"{}\"", token)
+            }
+            TiTxtReadErrorKind::MissingAddress => {
+                write!(f, "Data before the first @ADDR section header")
+            }
+            TiTxtReadErrorKind::AddressOutOfRange => {
+                write!(f, "Data extends past the end of the 32-bit address space")
+            }
+        }
+    }
+}
+
+/// An error encountered while reading a TI-TXT file, along with its location.
+#[derive(Debug)]
+pub struct TiTxtReadError {
+    /// The 1-based line number of the offending line.
+    pub line: usize,
+    /// The 1-based column at which the error was detected, if known.
+    pub column: Option<usize>,
+    /// The text of the offending line.
+    pub text: String,
+    pub kind: TiTxtReadErrorKind,
+}
+
+impl Display for TiTxtReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "Line {}, column {}: {}", self.line, column, self.kind)?,
+            None => write!(f, "Line {}: {}", self.line, self.kind)?,
+        }
+
+        if !self.text.is_empty() {
+            write!(f, " (line: \"{}\")", self.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for TiTxtReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            TiTxtReadErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+    use crate::record::file::StartAddr;
+
+    const EXAMPLE: &str = "@F000
+31 40 00 03 B2 40 80 5A 20 01 D2 D3 22 00 D2 E3
+21 00 3F 40
+@FFFE
+00 F0
+q
+";
+
+    fn read(text: &str) -> TiTxtFile {
+        TiTxtFile::read(text.as_bytes().lines()).unwrap()
+    }
+
+    fn read_error(text: &str) -> TiTxtReadError {
+        TiTxtFile::read(text.as_bytes().lines()).unwrap_err()
+    }
+
+    #[test]
+    fn read_example() {
+        let file = read(EXAMPLE);
+
+        assert_eq!(file.memory.segments().len(), 2);
+        assert_eq!(file.memory.min_address(), Some(0xF000));
+        assert_eq!(file.memory.read(0xF010, 4, 0), [0x21, 0x00, 0x3F, 0x40]);
+        assert_eq!(file.memory.read(0xFFFE, 2, 0), [0x00, 0xF0]);
+    }
+
+    #[test]
+    fn terminator_and_whitespace() {
+        // Lowercase digits, extra whitespace and empty lines are accepted, and
+        // nothing after the terminator is read
+        let file = read("@f000\n  31 40\t00 \n\n@F003\nab\n Q \n@0000\nZZ\n");
+
+        assert_eq!(file.memory.segments().len(), 1);
+        assert_eq!(file.memory.read(0xF000, 4, 0), [0x31, 0x40, 0x00, 0xAB]);
+
+        // The terminator is optional
+        assert_eq!(read("@0010\n01 02\n").memory.len(), 2);
+        assert!(read("q\n@0010\n01 02\n").memory.is_empty());
+    }
+
+    #[test]
+    fn sections() {
+        // Each line continues after the previous one, and later sections overwrite
+        // earlier ones
+        let file = read("@0100\n01 02\n03\n@0010\nAA\n@0102\n09\nq\n");
+        let segments: Vec<_> = file
+            .memory
+            .segments()
+            .iter()
+            .map(|seg| (seg.start, seg.data.clone()))
+            .collect();
+
+        assert_eq!(segments, [(0x10, vec![0xAA]), (0x100, vec![1, 2, 9])]);
+    }
+
+    #[test]
+    fn write_line_length() {
+        let mut file = TiTxtFile::default();
+        file.memory.insert(0x1_0000, &[1, 2, 3]);
+        file.memory.insert(0, &[0xAA]);
+
+        // A line length of 0 puts every byte on its own line, and addresses use as many
+        // digits as they need
+        let options = TiTxtWriteOptions {
+            line_length: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            file.to_titxt_string(&options),
+            "@0000\nAA\n@10000\n01\n02\n03\nq\n"
+        );
+        assert_eq!(read(&file.to_titxt_string(&options)), file);
+        assert_eq!(TiTxtFile::default().to_titxt_string(&options), "q\n");
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = read(EXAMPLE);
+
+        assert_eq!(file.to_titxt_string(&TiTxtWriteOptions::default()), EXAMPLE);
+
+        let mut file = file;
+        file.memory.insert(0x0001_0000, &[0xAA; 40]);
+
+        let options = TiTxtWriteOptions {
+            line_length: 7,
+            line_ending: LineEnding::CrLf,
+        };
+
+        assert_eq!(read(&file.to_titxt_string(&options)), file);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let check = |text: &str, line: usize, column: usize| {
+            let error = read_error(text);
+
+            assert_eq!((error.line, error.column), (line, Some(column)), "{}", text);
+            error.kind
+        };
+
+        assert!(matches!(
+            check("@XYZ\n", 1, 1),
+            TiTxtReadErrorKind::InvalidAddress
+        ));
+        assert!(matches!(
+            check("  @12G4\n", 1, 3),
+            TiTxtReadErrorKind::InvalidAddress
+        ));
+        assert!(matches!(
+            check("@100000000\n", 1, 1),
+            TiTxtReadErrorKind::InvalidAddress
+        ));
+        assert!(matches!(
+            check("00 01\nq\n", 1, 1),
+            TiTxtReadErrorKind::MissingAddress
+        ));
+        assert!(matches!(
+            check("@0000\n00 0102\n", 2, 4),
+            TiTxtReadErrorKind::InvalidByte(byte) if byte == "0102"
+        ));
+        assert!(matches!(
+            check("@0000\n00 1\n", 2, 4),
+            TiTxtReadErrorKind::InvalidByte(byte) if byte == "1"
+        ));
+
+        // The last byte of the address space can be written, but nothing after it
+        assert!(matches!(
+            check("@FFFFFFFF\n01\n02\n", 3, 1),
+            TiTxtReadErrorKind::AddressOutOfRange
+        ));
+        assert!(matches!(
+            check("@FFFFFFFE\n01 02 03\n", 2, 1),
+            TiTxtReadErrorKind::AddressOutOfRange
+        ));
+    }
+
+    #[test]
+    fn invalid_lines_lenient() {
+        let text = EXAMPLE.replace("D2 E3", "D2 XE3");
+
+        let error = read_error(&text);
+        assert_eq!((error.line, error.column), (2, Some(46)));
+        assert_eq!(
+            error.text,
+            "31 40 00 03 B2 40 80 5A 20 01 D2 D3 22 00 D2 XE3"
+        );
+
+        // The broken line is skipped without moving the address on
+        let (file, errors) = TiTxtFile::read_lenient(text.as_bytes().lines());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(file.memory.len(), 6);
+        assert_eq!(file.memory.min_address(), Some(0xF000));
+    }
+
+    #[test]
+    fn ihex_round_trip() {
+        let file = read(EXAMPLE);
+        let options = BinaryImportOptions {
+            start_address: Some(StartAddr::Linear(0xF000)),
+            ..Default::default()
+        };
+        let ihex = file.to_ihex(&options).unwrap();
+
+        assert!(matches!(
+            ihex.start_address(),
+            Some(StartAddr::Linear(0xF000))
+        ));
+        assert_eq!(TiTxtFile::from_ihex(&ihex), file);
+    }
+}
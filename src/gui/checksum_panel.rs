@@ -0,0 +1,222 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui::{Color32, Context, Grid, Label, RichText, SidePanel, TextEdit};
+
+use cpr_ihex::{
+    checksum::{memory_checksum, ChecksumAlgorithm, ChecksumValue},
+    memory::{parse_address, parse_number, MemoryMap},
+};
+
+use super::MainPanel;
+
+/// The bounds and fill byte of a calculation. A bound of `None` follows the lowest or
+/// highest address of the file, also after it was edited.
+#[derive(Clone)]
+struct ChecksumRange {
+    start: Option<u32>,
+    end: Option<u32>,
+    fill: u8,
+}
+
+impl ChecksumRange {
+    /// The range the bounds cover in `memory`, or `None` if the memory is empty
+    fn resolve(&self, memory: &MemoryMap) -> Result<Option<RangeInclusive<u32>>, String> {
+        match (
+            self.start.or(memory.min_address()),
+            self.end.or(memory.max_address()),
+        ) {
+            (Some(start), Some(end)) if start > end => Err(format!(
+                "The start address 0x{:08X} lies above the end address 0x{:08X}",
+                start, end
+            )),
+            (Some(start), Some(end)) => Ok(Some(start..=end)),
+            _ => Ok(None),
+        }
+    }
+}
+
+pub(super) struct ChecksumState {
+    start: String,
+    end: String,
+    fill: String,
+    /// The bounds of the last calculation, which is repeated when the file changes
+    calculated: Option<ChecksumRange>,
+    /// The range the last calculation covered
+    range: Option<RangeInclusive<u32>>,
+    /// Whether the file was edited after the last calculation
+    outdated: bool,
+    results: Vec<(ChecksumAlgorithm, ChecksumValue)>,
+    error: Option<String>,
+}
+
+impl Default for ChecksumState {
+    fn default() -> Self {
+        ChecksumState {
+            start: String::new(),
+            end: String::new(),
+            fill: "0xFF".to_string(),
+            calculated: None,
+            range: None,
+            outdated: false,
+            results: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+/// Parses an address field, where an empty field means no bound
+fn parse_bound(text: &str) -> Result<Option<u32>, String> {
+    match text.trim() {
+        "" => Ok(None),
+        text => parse_address(text).map(Some).map_err(|e| e.to_string()),
+    }
+}
+
+impl ChecksumState {
+    fn parse_range(&self) -> Result<ChecksumRange, String> {
+        let start = parse_bound(&self.start)?;
+        let end = parse_bound(&self.end)?;
+
+        let fill = parse_number(&self.fill)
+            .map_err(|_| format!("Invalid fill byte: \"{}\"", self.fill.trim()))?;
+
+        Ok(ChecksumRange { start, end, fill })
+    }
+
+    fn calculate(&mut self, memory: &MemoryMap) {
+        match self.parse_range() {
+            Ok(range) => {
+                self.calculated = Some(range);
+                self.refresh(memory);
+            }
+            Err(e) => {
+                self.error = Some(e);
+                self.calculated = None;
+                self.range = None;
+                self.results.clear();
+            }
+        }
+    }
+
+    /// Marks the results as outdated after the file was edited. Calculating all algorithms
+    /// over a large range is too slow to repeat on every edit, so it is left to the user.
+    pub(super) fn invalidate(&mut self) {
+        self.outdated = self.calculated.is_some();
+    }
+
+    /// Repeats the last calculation, e.g. after the file was edited
+    fn refresh(&mut self, memory: &MemoryMap) {
+        let Some(calculated) = &self.calculated else {
+            return;
+        };
+
+        self.outdated = false;
+
+        let range = match calculated.resolve(memory) {
+            Ok(range) => range,
+            Err(e) => {
+                self.error = Some(e);
+                self.range = None;
+                self.results.clear();
+                return;
+            }
+        };
+
+        self.error = None;
+        self.range = range.clone();
+        self.results = ChecksumAlgorithm::ALL
+            .iter()
+            .map(|algorithm| {
+                let value = memory_checksum(memory, *algorithm, range.clone(), calculated.fill);
+
+                (*algorithm, value)
+            })
+            .collect();
+    }
+}
+
+/// Shows the checksum panel, with the range to calculate over and the checksums of every algorithm
+pub(super) fn checksum_panel(panel: &mut MainPanel, ctx: &Context) {
+    SidePanel::right("checksum_panel")
+        .resizable(true)
+        .default_width(300.0)
+        .show(ctx, |ui| {
+            let state = &mut panel.meta.checksum;
+
+            ui.heading("Checksum");
+            ui.separator();
+
+            Grid::new("checksum_range").num_columns(2).show(ui, |ui| {
+                ui.label("Start");
+                ui.add(TextEdit::singleline(&mut state.start).hint_text("Lowest address"));
+                ui.end_row();
+
+                ui.label("End");
+                ui.add(TextEdit::singleline(&mut state.end).hint_text("Highest address"));
+                ui.end_row();
+
+                ui.label("Fill byte");
+                ui.add(TextEdit::singleline(&mut state.fill).hint_text("0xFF"));
+                ui.end_row();
+            });
+
+            if ui.button("Calculate").clicked() {
+                state.calculate(&panel.memory);
+            }
+
+            if let Some(error) = &state.error {
+                ui.colored_label(Color32::RED, error);
+            }
+
+            if state.outdated {
+                ui.horizontal(|ui| {
+                    ui.colored_label(Color32::YELLOW, "The file was edited");
+
+                    if ui.button("Recalculate").clicked() {
+                        state.refresh(&panel.memory);
+                    }
+                });
+            }
+
+            if state.calculated.is_none() || state.error.is_some() {
+                return;
+            }
+
+            ui.separator();
+
+            match &state.range {
+                Some(range) => ui.label(format!(
+                    "0x{:08X} - 0x{:08X} ({} bytes)",
+                    range.start(),
+                    range.end(),
+                    *range.end() as u64 - *range.start() as u64 + 1
+                )),
+                None => ui.label("No data"),
+            };
+
+            let mut copied = None;
+
+            Grid::new("checksum_results")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (algorithm, value) in state.results.iter() {
+                        ui.label(algorithm.name());
+
+                        let text = value.to_string();
+
+                        ui.add(Label::new(RichText::new(&text).monospace()).wrap(true));
+
+                        if ui.small_button("Copy").clicked() {
+                            copied = Some(text);
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+            if let Some(text) = copied {
+                ui.output_mut(|output| output.copied_text = text);
+            }
+        });
+}
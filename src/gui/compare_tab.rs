@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+
+use eframe::egui::{Align, Color32, RichText, Ui};
+use egui_extras::{Column, TableBuilder};
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+
+use cpr_ihex::{
+    diff::{DiffKind, ImageDiff},
+    memory::MemoryMap,
+    record::file::{IHexFile, StartAddr},
+};
+
+use super::{
+    memory_tab::{MemoryRow, BYTES_PER_ROW, ROW_HEIGHT},
+    open_file,
+};
+
+/// A second file that the open file is compared with
+pub(super) struct CompareState {
+    path: PathBuf,
+    start_address: Option<StartAddr>,
+    memory: MemoryMap,
+    /// The differences from the open file (A) to the compared file (B)
+    diff: ImageDiff,
+    /// The rows of the union of both images
+    rows: Vec<MemoryRow>,
+    /// The index of the difference that was navigated to last
+    current: Option<usize>,
+    /// The row to scroll to in the next frame
+    scroll_to_row: Option<usize>,
+}
+
+impl CompareState {
+    fn new(path: PathBuf, other: &IHexFile, file: &IHexFile, memory: &MemoryMap) -> Self {
+        let mut state = CompareState {
+            path,
+            start_address: other.start_address(),
+            memory: other.data_bytes(),
+            diff: ImageDiff::default(),
+            rows: Vec::new(),
+            current: None,
+            scroll_to_row: None,
+        };
+
+        state.rebuild(file, memory);
+        state
+    }
+
+    /// Compares the open file with the second file again, e.g. after it was edited
+    pub(super) fn rebuild(&mut self, file: &IHexFile, memory: &MemoryMap) {
+        let mut union = memory.clone();
+
+        for segment in self.memory.segments() {
+            union.insert(segment.start, &segment.data);
+        }
+
+        self.rows = MemoryRow::build_all(&union);
+        self.diff = ImageDiff {
+            start_address_a: file.start_address(),
+            start_address_b: self.start_address,
+            ..ImageDiff::from_memory(memory, &self.memory)
+        };
+        self.current = None;
+    }
+
+    /// Scrolls to the next or previous difference, wrapping around at the ends
+    fn navigate(&mut self, forward: bool) {
+        let len = self.diff.ranges.len();
+
+        if len == 0 {
+            return;
+        }
+
+        let next = match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+
+        self.current = Some(next);
+        self.scroll_to_row = MemoryRow::find(&self.rows, self.diff.ranges[next].range.start as u32);
+    }
+}
+
+/// Lets the user pick a second file, and compares the open file with it.
+/// Returns `None` if no file was picked or it could not be opened.
+pub(super) fn open_compare(file: &IHexFile, memory: &MemoryMap) -> Option<CompareState> {
+    let path = open_file::pick_file()?;
+
+    match open_file::open_path(&path) {
+        Ok(other) => Some(CompareState::new(path, &other.file, file, memory)),
+        Err(e) => {
+            MessageDialog::new()
+                .set_level(MessageLevel::Error)
+                .set_title("Could not open file")
+                .set_description(e)
+                .set_buttons(MessageButtons::Ok)
+                .show();
+
+            None
+        }
+    }
+}
+
+fn start_address_text(start: Option<StartAddr>) -> String {
+    match start {
+        Some(StartAddr::Segment(addr)) => format!(
+            "CS 0x{:x} - IP 0x{:x}",
+            addr.code_segment, addr.instruction_pointer
+        ),
+        Some(StartAddr::Linear(addr)) => format!("0x{:x}", addr),
+        None => "none".to_string(),
+    }
+}
+
+/// Shows the bytes of one image in a row, highlighting the bytes that differ
+fn byte_cells(memory: &MemoryMap, diff: &ImageDiff, row_address: u32, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+
+        for offset in 0..BYTES_PER_ROW {
+            if offset == BYTES_PER_ROW / 2 {
+                ui.add_space(6.0);
+            }
+
+            let address = row_address + offset;
+            let mut text = match memory.get(address) {
+                Some(byte) => RichText::new(format!("{:02X}", byte)),
+                None => RichText::new("  "),
+            }
+            .monospace();
+
+            text = match diff.kind_at(address) {
+                Some(DiffKind::Changed) => text.background_color(Color32::RED.gamma_multiply(0.4)),
+                Some(DiffKind::OnlyInA | DiffKind::OnlyInB) => {
+                    text.background_color(Color32::YELLOW.gamma_multiply(0.3))
+                }
+                None => text,
+            };
+
+            ui.label(text);
+        }
+    });
+}
+
+/// Shows the open file and the compared file side by side
+pub(super) fn compare_tab(
+    file: &IHexFile,
+    memory: &MemoryMap,
+    compare: &mut Option<CompareState>,
+    ui: &mut Ui,
+) {
+    let Some(state) = compare else {
+        ui.vertical_centered(|ui| {
+            ui.label("Compare the data of this file with another file, by address");
+
+            if ui.button("Open file to compare...").clicked() {
+                *compare = open_compare(file, memory);
+            }
+        });
+
+        return;
+    };
+
+    let mut close = false;
+
+    ui.horizontal(|ui| {
+        ui.label(format!("Comparing with {}", state.path.display()));
+
+        if ui.small_button("Open other...").clicked() {
+            if let Some(other) = open_compare(file, memory) {
+                *state = other;
+            }
+        }
+
+        if ui.small_button("Close").clicked() {
+            close = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if state.diff.is_empty() {
+            ui.label("The files are identical");
+        } else {
+            ui.label(format!(
+                "Changed: {} bytes, only in this file: {} bytes, only in the other file: {} bytes",
+                state.diff.byte_count(DiffKind::Changed),
+                state.diff.byte_count(DiffKind::OnlyInA),
+                state.diff.byte_count(DiffKind::OnlyInB)
+            ));
+        }
+
+        if state.diff.start_address_changed() {
+            ui.add_space(5.0);
+            ui.colored_label(
+                Color32::RED,
+                format!(
+                    "Start address: {} vs. {}",
+                    start_address_text(state.diff.start_address_a),
+                    start_address_text(state.diff.start_address_b)
+                ),
+            );
+        }
+
+        if !state.diff.ranges.is_empty() {
+            ui.add_space(5.0);
+
+            if ui.small_button("Previous difference").clicked() {
+                state.navigate(false);
+            }
+
+            if ui.small_button("Next difference").clicked() {
+                state.navigate(true);
+            }
+
+            if let Some(current) = state.current {
+                ui.label(format!("{}/{}", current + 1, state.diff.ranges.len()));
+            }
+        }
+    });
+
+    if close {
+        *compare = None;
+        return;
+    }
+
+    let max_scroll_height = ui.available_height() - ROW_HEIGHT;
+
+    let mut table = TableBuilder::new(ui)
+        .striped(true)
+        .auto_shrink([false, false])
+        .max_scroll_height(max_scroll_height)
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(420.0))
+        .column(Column::remainder());
+
+    if let Some(row) = state.scroll_to_row.take() {
+        table = table.scroll_to_row(row, Some(Align::Center));
+    }
+
+    table
+        .header(ROW_HEIGHT, |mut header| {
+            header.col(|ui| {
+                ui.heading("Address");
+            });
+            header.col(|ui| {
+                ui.heading("This file");
+            });
+            header.col(|ui| {
+                ui.heading("Other file");
+            });
+        })
+        .body(|body| {
+            body.rows(ROW_HEIGHT, state.rows.len(), |mut row| {
+                let row_address = match &state.rows[row.index()] {
+                    MemoryRow::Bytes(address) => *address,
+                    MemoryRow::Gap(gap) => {
+                        row.col(|_| {});
+                        row.col(|ui| {
+                            ui.colored_label(
+                                Color32::GRAY,
+                                format!(
+                                    "Gap: 0x{:08X} - 0x{:08X} ({} bytes unmapped)",
+                                    gap.start,
+                                    gap.end - 1,
+                                    gap.end - gap.start
+                                ),
+                            );
+                        });
+                        row.col(|_| {});
+                        return;
+                    }
+                };
+
+                row.col(|ui| {
+                    ui.monospace(format!("{:08X}", row_address));
+                });
+                row.col(|ui| byte_cells(memory, &state.diff, row_address, ui));
+                row.col(|ui| byte_cells(&state.memory, &state.diff, row_address, ui));
+            });
+        });
+}
@@ -0,0 +1,233 @@
+use eframe::egui::{Color32, TextEdit, Ui};
+
+use cpr_ihex::{
+    memory::parse_number,
+    record::{DataRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord, IHexRecord},
+};
+
+use super::AddressDisplayMeta;
+
+/// The value used to pad data records that are made longer than their data
+const PAD_BYTE: u8 = 0xFF;
+
+/// The in-progress edit of a data record
+pub(super) struct DataEditState {
+    offset: String,
+    length: String,
+    bytes: String,
+}
+
+impl DataEditState {
+    pub(super) fn new(record: &DataRecord) -> Self {
+        DataEditState {
+            offset: format!("0x{:04X}", record.naive_address),
+            length: record.data.len().to_string(),
+            bytes: hex_bytes_string(&record.data),
+        }
+    }
+
+    /// Builds the edited record, or returns a message describing why the input is invalid
+    fn parse(&self, original: &DataRecord) -> Result<DataRecord, String> {
+        let offset: u16 = parse_number(&self.offset)
+            .map_err(|_| format!("Invalid load offset: \"{}\"", self.offset))?;
+
+        let length: u8 = self
+            .length
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid length (must be 0-255): \"{}\"", self.length))?;
+
+        let mut data = parse_hex_bytes(&self.bytes)?;
+
+        if data.len() > length as usize {
+            return Err(format!(
+                "{} bytes were entered, but the length is {}",
+                data.len(),
+                length
+            ));
+        }
+
+        data.resize(length as usize, PAD_BYTE);
+
+        let mut record = original.clone();
+        record.naive_address = offset;
+        record.data = data;
+
+        Ok(record)
+    }
+}
+
+pub(super) fn hex_bytes_string(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses bytes separated by whitespace or commas, each optionally prefixed with `0x`.
+/// Tokens of more than one byte (e.g. `DEADBEEF`) are split into their bytes.
+pub(super) fn parse_hex_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    let tokens = text
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty());
+
+    for token in tokens {
+        let digits = token
+            .strip_prefix("0x")
+            .or(token.strip_prefix("0X"))
+            .unwrap_or(token);
+
+        let digits = if digits.len() == 1 {
+            format!("0{}", digits)
+        } else {
+            digits.to_string()
+        };
+
+        let decoded =
+            hex::decode(&digits).map_err(|_| format!("Invalid hex byte(s): \"{}\"", token))?;
+
+        bytes.extend(decoded);
+    }
+
+    Ok(bytes)
+}
+
+/// An edit to the records of the opened file
+pub(super) enum RecordEdit {
+    Replace { index: usize, record: IHexRecord },
+    Insert { index: usize, record: IHexRecord },
+    Remove { index: usize },
+    Move { from: usize, to: usize },
+}
+
+/// Shows the editor for a data record. Returns the edit if it was applied.
+pub(super) fn data_edit_ui(
+    index: usize,
+    state: &mut Option<DataEditState>,
+    record: &DataRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    let edit_state = state.as_mut()?;
+    let parsed = edit_state.parse(record);
+
+    let mut result = None;
+    let mut close = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Offset:");
+        ui.add(TextEdit::singleline(&mut edit_state.offset).desired_width(50.0));
+
+        ui.label("Length:");
+        ui.add(TextEdit::singleline(&mut edit_state.length).desired_width(30.0));
+
+        let text_color = match parsed {
+            Ok(_) => None,
+            Err(_) => Some(Color32::RED),
+        };
+
+        ui.add(
+            TextEdit::singleline(&mut edit_state.bytes)
+                .desired_width(ui.available_width() - 150.0)
+                .text_color_opt(text_color),
+        )
+        .on_hover_text(format!(
+            "Hex bytes. If the length is larger than the amount of bytes, the data is padded with 0x{:02X}. \
+             More bytes than the length are not accepted",
+            PAD_BYTE
+        ));
+
+        match &parsed {
+            Ok(new_record) => {
+                if ui.button("Apply").clicked() {
+                    result = Some(RecordEdit::Replace {
+                        index,
+                        record: IHexRecord::Data(new_record.clone()),
+                    });
+                    close = true;
+                }
+            }
+            Err(e) => {
+                ui.add_enabled(false, eframe::egui::Button::new("Apply"))
+                    .on_disabled_hover_text(e.as_str());
+            }
+        }
+
+        if ui.button("Cancel").clicked() {
+            close = true;
+        }
+    });
+
+    if close {
+        *state = None;
+    }
+
+    result
+}
+
+/// Shows the editor for the base of an extended segment or linear address record.
+/// The base is entered as the 16-bit value stored in the record.
+/// Returns the edit if it was applied.
+pub(super) fn address_edit_ui(
+    index: usize,
+    meta: &mut AddressDisplayMeta,
+    record: &IHexRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    let text = meta.edit.as_mut()?;
+    let parsed = parse_number::<u16>(text).ok();
+
+    let mut result = None;
+    let mut close = false;
+
+    ui.horizontal(|ui| {
+        let label = match record {
+            IHexRecord::ExtendedSegmentAddress(_) => "Segment:",
+            _ => "Upper address:",
+        };
+
+        ui.label(label);
+
+        let text_color = parsed.is_none().then_some(Color32::RED);
+        ui.add(
+            TextEdit::singleline(text)
+                .desired_width(50.0)
+                .text_color_opt(text_color),
+        );
+
+        let apply = ui.add_enabled(parsed.is_some(), eframe::egui::Button::new("Apply"));
+
+        if let (true, Some(value)) = (apply.clicked(), parsed) {
+            let new_record = match record {
+                IHexRecord::ExtendedSegmentAddress(_) => {
+                    IHexRecord::ExtendedSegmentAddress(ExtendedSegmentAddressRecord {
+                        segment_base: (value as usize) << 4,
+                    })
+                }
+                _ => IHexRecord::ExtendedLinearAddress(ExtendedLinearAddressRecord {
+                    address_base: value,
+                }),
+            };
+
+            result = Some(RecordEdit::Replace {
+                index,
+                record: new_record,
+            });
+            close = true;
+        }
+
+        if ui.button("Cancel").clicked() {
+            close = true;
+        }
+    });
+
+    if close {
+        meta.edit = None;
+    }
+
+    result
+}
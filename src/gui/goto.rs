@@ -0,0 +1,88 @@
+use eframe::egui::{Color32, Context, Id, Key, KeyboardShortcut, Modifiers, TextEdit, Ui};
+
+use cpr_ihex::memory::parse_address;
+
+use super::{MainPanel, MainPanelTab};
+
+const GOTO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::G);
+
+/// The contents of the go-to address field
+#[derive(Default)]
+pub(super) struct GoToState {
+    text: String,
+    error: Option<String>,
+}
+
+impl MainPanel {
+    /// Selects the record containing the effective address `address`, and scrolls to it
+    pub(super) fn go_to_address(&mut self, address: u32) -> Result<(), String> {
+        self.meta.memory.selected_address = Some(address);
+        self.meta.memory.selected_length = 1;
+        self.meta.selected_record = self.file.record_at_address(address);
+
+        let record = self
+            .meta
+            .selected_record
+            .ok_or_else(|| format!("Address 0x{:08X} is not mapped", address))?;
+
+        self.meta.data.scroll_to_record = Some(record);
+        self.meta.memory.scroll_to_address(address);
+
+        // The memory map has nothing to scroll to, so show the record instead
+        if self.tab == MainPanelTab::MemoryMap {
+            self.tab = MainPanelTab::Data;
+        }
+
+        Ok(())
+    }
+}
+
+/// Shows the go-to address field. Focused with Ctrl+G.
+pub(super) fn goto_bar(panel: &mut MainPanel, ctx: &Context, ui: &mut Ui) {
+    let id = Id::new("goto_address_field");
+
+    if ctx.input_mut(|i| i.consume_shortcut(&GOTO_SHORTCUT)) {
+        ctx.memory_mut(|m| m.request_focus(id));
+    }
+
+    let state = &mut panel.meta.goto;
+    let mut go = false;
+
+    ui.label("Go to:");
+
+    let response = ui
+        .add(
+            TextEdit::singleline(&mut state.text)
+                .id(id)
+                .desired_width(120.0)
+                .hint_text("0x1000, 4096, F000:0100"),
+        )
+        .on_hover_text(format!(
+            "Address in hex (0x prefix), decimal or CS:IP notation ({})",
+            ctx.format_shortcut(&GOTO_SHORTCUT)
+        ));
+
+    if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+        go = true;
+    }
+
+    if response.changed() {
+        state.error = None;
+    }
+
+    if ui.button("Go").clicked() {
+        go = true;
+    }
+
+    if let Some(error) = &state.error {
+        ui.colored_label(Color32::RED, error);
+    }
+
+    if go {
+        let result = parse_address(&state.text)
+            .map_err(|e| e.to_string())
+            .and_then(|address| panel.go_to_address(address));
+
+        panel.meta.goto.error = result.err();
+    }
+}
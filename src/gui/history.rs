@@ -0,0 +1,327 @@
+use eframe::egui::{
+    Color32, Context, Key, KeyboardShortcut, Modifiers, RichText, ScrollArea, SidePanel,
+};
+
+use cpr_ihex::record::IHexRecord;
+
+use super::{
+    edit::RecordEdit, main_panel::record_type_name, DataTableRow, IHexRecordDisplayMeta, MainPanel,
+};
+
+pub(super) const UNDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub(super) const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// A reversible change to the records of the file
+#[derive(Clone)]
+enum EditOp {
+    Replace {
+        index: usize,
+        old: IHexRecord,
+        new: IHexRecord,
+        was_modified: bool,
+    },
+    Insert {
+        index: usize,
+        record: IHexRecord,
+    },
+    Remove {
+        index: usize,
+        record: IHexRecord,
+    },
+    Move {
+        from: usize,
+        to: usize,
+    },
+}
+
+struct HistoryEntry {
+    description: String,
+    op: EditOp,
+}
+
+/// The undo/redo history of the opened file
+pub(super) struct History {
+    entries: Vec<HistoryEntry>,
+    /// The amount of entries that are currently applied
+    position: usize,
+    /// The position at which the file was last saved, if that state is still reachable
+    saved_position: Option<usize>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            entries: Vec::new(),
+            position: 0,
+            saved_position: Some(0),
+        }
+    }
+}
+
+impl History {
+    fn push(&mut self, entry: HistoryEntry) {
+        self.entries.truncate(self.position);
+
+        if self
+            .saved_position
+            .is_some_and(|saved| saved > self.position)
+        {
+            self.saved_position = None;
+        }
+
+        self.entries.push(entry);
+        self.position += 1;
+    }
+
+    pub(super) fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub(super) fn can_redo(&self) -> bool {
+        self.position < self.entries.len()
+    }
+
+    /// Whether the current state differs from the last saved (or opened) state
+    pub(super) fn is_dirty(&self) -> bool {
+        self.saved_position != Some(self.position)
+    }
+
+    pub(super) fn mark_saved(&mut self) {
+        self.saved_position = Some(self.position);
+    }
+}
+
+impl MainPanel {
+    /// Applies an edit to the file, and records it in the history
+    pub(super) fn apply_edit(&mut self, edit: RecordEdit) {
+        let (description, op) = match edit {
+            RecordEdit::Replace { index, record } => (
+                format!("Edit {} record #{:06}", record_type_name(&record), index),
+                EditOp::Replace {
+                    index,
+                    old: self.file.records[index].clone(),
+                    new: record,
+                    was_modified: self.meta.data.record_meta[index].modified(),
+                },
+            ),
+            RecordEdit::Insert { index, record } => (
+                format!("Insert {} record #{:06}", record_type_name(&record), index),
+                EditOp::Insert { index, record },
+            ),
+            RecordEdit::Remove { index } => {
+                let record = self.file.records[index].clone();
+                (
+                    format!("Delete {} record #{:06}", record_type_name(&record), index),
+                    EditOp::Remove { index, record },
+                )
+            }
+            RecordEdit::Move { from, to } => (
+                format!("Move record #{:06} to #{:06}", from, to),
+                EditOp::Move { from, to },
+            ),
+        };
+
+        self.perform(&op, false);
+        self.history.push(HistoryEntry { description, op });
+        self.records_changed();
+    }
+
+    pub(super) fn undo(&mut self) {
+        if !self.history.can_undo() {
+            return;
+        }
+
+        self.history.position -= 1;
+
+        let op = self.history.entries[self.history.position].op.clone();
+        self.perform(&op, true);
+
+        self.records_changed();
+    }
+
+    pub(super) fn redo(&mut self) {
+        if !self.history.can_redo() {
+            return;
+        }
+
+        let op = self.history.entries[self.history.position].op.clone();
+        self.perform(&op, false);
+
+        self.history.position += 1;
+
+        self.records_changed();
+    }
+
+    /// Undoes or redoes edits until exactly `position` edits are applied
+    fn go_to_history_position(&mut self, position: usize) {
+        while self.history.position > position {
+            self.undo();
+        }
+
+        while self.history.position < position {
+            self.redo();
+        }
+    }
+
+    /// Performs the operation, or reverts it if `revert` is set
+    fn perform(&mut self, op: &EditOp, revert: bool) {
+        match (op, revert) {
+            (EditOp::Replace { index, new, .. }, false) => {
+                self.replace_record(*index, new.clone());
+                self.meta.data.record_meta[*index].set_modified(true);
+            }
+            (
+                EditOp::Replace {
+                    index,
+                    old,
+                    was_modified,
+                    ..
+                },
+                true,
+            ) => {
+                self.replace_record(*index, old.clone());
+                self.meta.data.record_meta[*index].set_modified(*was_modified);
+            }
+            (EditOp::Insert { index, record }, false)
+            | (EditOp::Remove { index, record }, true) => {
+                self.insert_record(*index, record.clone());
+            }
+            (EditOp::Insert { index, .. }, true) | (EditOp::Remove { index, .. }, false) => {
+                self.remove_record(*index);
+            }
+            (EditOp::Move { from, to }, false) => self.move_record(*from, *to),
+            (EditOp::Move { from, to }, true) => self.move_record(*to, *from),
+        }
+    }
+
+    fn replace_record(&mut self, index: usize, record: IHexRecord) {
+        let meta = &mut self.meta.data.record_meta[index];
+
+        if !meta.check_matches(&record) {
+            *meta = IHexRecordDisplayMeta::default_for(&record);
+        }
+
+        self.file.records[index] = record;
+    }
+
+    fn insert_record(&mut self, index: usize, record: IHexRecord) {
+        let mut meta = IHexRecordDisplayMeta::default_for(&record);
+        meta.set_modified(true);
+
+        self.meta.data.record_meta.insert(index, meta);
+        self.file.records.insert(index, record);
+
+        for diagnostic in self.diagnostics.iter_mut() {
+            if diagnostic.record_index > index {
+                diagnostic.record_index += 1;
+            }
+        }
+    }
+
+    fn remove_record(&mut self, index: usize) -> (IHexRecord, IHexRecordDisplayMeta) {
+        for diagnostic in self.diagnostics.iter_mut() {
+            if diagnostic.record_index > index {
+                diagnostic.record_index -= 1;
+            }
+        }
+
+        (
+            self.file.records.remove(index),
+            self.meta.data.record_meta.remove(index),
+        )
+    }
+
+    fn move_record(&mut self, from: usize, to: usize) {
+        let (record, meta) = self.remove_record(from);
+
+        for diagnostic in self.diagnostics.iter_mut() {
+            if diagnostic.record_index > to {
+                diagnostic.record_index += 1;
+            }
+        }
+
+        self.file.records.insert(to, record);
+        self.meta.data.record_meta.insert(to, meta);
+    }
+
+    /// Updates everything derived from the records after they were changed
+    fn records_changed(&mut self) {
+        self.file.refresh();
+        self.memory = self.file.data_bytes();
+        self.meta.data.rows = DataTableRow::build_all(self.file.records.len(), &self.diagnostics);
+        self.meta.memory.rebuild(&self.memory);
+        self.meta.layout.rebuild(&self.file, &self.memory);
+        self.meta.search.refresh(&self.memory);
+        self.meta.checksum.invalidate();
+
+        if let Some(compare) = &mut self.meta.compare {
+            compare.rebuild(&self.file, &self.memory);
+        }
+
+        self.meta.selected_record = self
+            .meta
+            .memory
+            .selected_address
+            .and_then(|address| self.file.record_at_address(address));
+    }
+
+    pub(super) fn handle_history_shortcuts(&mut self, ctx: &Context) {
+        // Text fields have their own undo, so don't steal it from them
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        // Most specific shortcut first, see `InputState::consume_shortcut`
+        if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+            self.redo();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+            self.undo();
+        }
+    }
+}
+
+/// Shows the list of edits, where clicking an edit moves the file to the state right after it
+pub(super) fn history_panel(panel: &mut MainPanel, ctx: &Context) {
+    let mut go_to = None;
+
+    SidePanel::right("history_panel")
+        .resizable(true)
+        .default_width(250.0)
+        .show(ctx, |ui| {
+            ui.heading("History");
+            ui.separator();
+
+            ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if ui
+                        .selectable_label(panel.history.position == 0, "Opened file")
+                        .clicked()
+                    {
+                        go_to = Some(0);
+                    }
+
+                    for (i, entry) in panel.history.entries.iter().enumerate() {
+                        let applied = i < panel.history.position;
+                        let text = if applied {
+                            RichText::new(&entry.description)
+                        } else {
+                            RichText::new(&entry.description).color(Color32::GRAY)
+                        };
+
+                        if ui
+                            .selectable_label(panel.history.position == i + 1, text)
+                            .clicked()
+                        {
+                            go_to = Some(i + 1);
+                        }
+                    }
+                });
+        });
+
+    if let Some(position) = go_to {
+        panel.go_to_history_position(position);
+    }
+}
@@ -0,0 +1,327 @@
+use std::ops::Range;
+
+use eframe::egui::{vec2, Color32, ComboBox, Rect, Rounding, Sense, Ui};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+
+use cpr_ihex::{
+    memory::MemoryMap,
+    record::{
+        file::IHexFile,
+        validate::{ValidationIssueKind, ValidationOptions},
+    },
+};
+
+const ROW_HEIGHT: f32 = 20.0;
+const STRIP_HEIGHT: f32 = 24.0;
+const PAGE_SIZES: [u32; 9] = [256, 512, 1024, 2048, 4096, 8192, 16384, 65536, 131072];
+const USED_COLOR: Color32 = Color32::from_rgb(60, 140, 80);
+const OVERLAP_COLOR: Color32 = Color32::RED;
+
+/// Two data records that write to the same addresses
+struct Overlap {
+    range: Range<u64>,
+    records: (usize, usize),
+    conflicting: bool,
+}
+
+/// A row in the region table
+enum LayoutRow {
+    /// The index of a memory segment
+    Region(usize),
+    Gap(Range<u64>),
+}
+
+pub(super) struct LayoutTabMeta {
+    rows: Vec<LayoutRow>,
+    overlaps: Vec<Overlap>,
+    page_size: u32,
+}
+
+impl LayoutTabMeta {
+    pub(super) fn new(file: &IHexFile, memory: &MemoryMap) -> Self {
+        let mut meta = LayoutTabMeta {
+            rows: Vec::new(),
+            overlaps: Vec::new(),
+            page_size: 4096,
+        };
+
+        meta.rebuild(file, memory);
+        meta
+    }
+
+    pub(super) fn rebuild(&mut self, file: &IHexFile, memory: &MemoryMap) {
+        let gaps = memory.gaps().map(LayoutRow::Gap);
+        let regions = (0..memory.segments().len()).map(LayoutRow::Region);
+
+        // Regions and gaps alternate, starting and ending with a region
+        self.rows = Itertools::interleave(regions, gaps).collect();
+
+        self.overlaps = file
+            .validate(&ValidationOptions::default())
+            .into_iter()
+            .filter_map(|issue| match issue.kind {
+                ValidationIssueKind::OverlappingData {
+                    other_record,
+                    address,
+                    length,
+                    conflicting,
+                } => Some(Overlap {
+                    range: address as u64..address as u64 + length,
+                    records: (other_record, issue.record_index?),
+                    conflicting,
+                }),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 && bytes % (1024 * 1024) == 0 {
+        format!("{} MiB", bytes / (1024 * 1024))
+    } else if bytes >= 1024 && bytes % 1024 == 0 {
+        format!("{} KiB", bytes / 1024)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Maps addresses within `span` to horizontal positions within `rect`
+fn address_to_x(rect: Rect, span: &Range<u64>, address: u64) -> f32 {
+    let fraction = (address - span.start) as f64 / (span.end - span.start) as f64;
+    rect.left() + rect.width() * fraction as f32
+}
+
+/// Paints `range` as a bar within `rect`, scaled to `span`. Bars are always at least a pixel wide.
+fn paint_range(ui: &Ui, rect: Rect, span: &Range<u64>, range: &Range<u64>, color: Color32) {
+    let left = address_to_x(rect, span, range.start);
+    let right = address_to_x(rect, span, range.end).max(left + 1.0);
+
+    ui.painter().rect_filled(
+        Rect::from_x_y_ranges(left..=right, rect.y_range()),
+        Rounding::same(1.0),
+        color,
+    );
+}
+
+/// Allocates a horizontal strip for painting the address space into
+fn allocate_strip(ui: &mut Ui, height: f32) -> (Rect, eframe::egui::Response) {
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(ui.available_width(), height), Sense::hover());
+
+    ui.painter()
+        .rect_filled(rect, Rounding::same(1.0), ui.visuals().faint_bg_color);
+
+    (rect, response)
+}
+
+fn page_strip(memory: &MemoryMap, meta: &LayoutTabMeta, ui: &mut Ui) {
+    let page_size = meta.page_size as u64;
+    let pages = memory.occupied_pages(meta.page_size);
+
+    let (Some(first), Some(last)) = (pages.first(), pages.last()) else {
+        return;
+    };
+
+    let span = first.start..last.end;
+    let num_pages: u64 = pages.iter().map(|range| range.end - range.start).sum();
+
+    ui.label(format!(
+        "Occupied {} pages: {} of {} (0x{:08X} - 0x{:08X})",
+        format_size(page_size),
+        num_pages,
+        span.end - span.start,
+        span.start * page_size,
+        span.end * page_size - 1
+    ));
+
+    let (rect, response) = allocate_strip(ui, STRIP_HEIGHT);
+
+    for range in &pages {
+        paint_range(ui, rect, &span, range, USED_COLOR);
+    }
+
+    for overlap in &meta.overlaps {
+        let range = overlap.range.start / page_size..overlap.range.end.div_ceil(page_size);
+        paint_range(ui, rect, &span, &range, OVERLAP_COLOR);
+    }
+
+    response.on_hover_ui_at_pointer(|ui| {
+        let Some(pos) = ui.ctx().pointer_hover_pos() else {
+            return;
+        };
+
+        let fraction = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64;
+        let page =
+            (span.start + (fraction * (span.end - span.start) as f64) as u64).min(span.end - 1);
+        let used = pages.iter().any(|range| range.contains(&page));
+
+        ui.label(format!(
+            "Page 0x{:08X} - 0x{:08X}: {}",
+            page * page_size,
+            (page + 1) * page_size - 1,
+            if used { "occupied" } else { "empty" }
+        ));
+    });
+}
+
+fn region_table(memory: &MemoryMap, meta: &LayoutTabMeta, ui: &mut Ui) {
+    let (Some(min), Some(max)) = (memory.min_address(), memory.max_address()) else {
+        return;
+    };
+
+    let span = min as u64..max as u64 + 1;
+    let page_size = meta.page_size as u64;
+    let max_scroll_height = ui.available_height() - ROW_HEIGHT;
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .auto_shrink([false, false])
+        .max_scroll_height(max_scroll_height)
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(120.0))
+        .column(Column::remainder())
+        .header(ROW_HEIGHT, |mut header| {
+            for title in ["Region", "Start", "End", "Size", "Pages", "Layout"] {
+                header.col(|ui| {
+                    ui.heading(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(ROW_HEIGHT, meta.rows.len(), |mut row| {
+                let (name, range) = match &meta.rows[row.index()] {
+                    LayoutRow::Region(i) => {
+                        let seg = &memory.segments()[*i];
+                        (format!("#{}", i), seg.start as u64..seg.end())
+                    }
+                    LayoutRow::Gap(gap) => ("Gap".to_string(), gap.clone()),
+                };
+
+                let is_gap = matches!(meta.rows[row.index()], LayoutRow::Gap(_));
+                let overlaps: Vec<_> = meta
+                    .overlaps
+                    .iter()
+                    .filter(|overlap| {
+                        overlap.range.start < range.end && range.start < overlap.range.end
+                    })
+                    .collect();
+
+                let text_color = if is_gap {
+                    Some(Color32::GRAY)
+                } else if !overlaps.is_empty() {
+                    Some(OVERLAP_COLOR)
+                } else {
+                    None
+                };
+
+                let pages = if is_gap {
+                    String::new()
+                } else {
+                    format!(
+                        "{} ({} - {})",
+                        (range.end - 1) / page_size - range.start / page_size + 1,
+                        range.start / page_size,
+                        (range.end - 1) / page_size
+                    )
+                };
+
+                let cells = [
+                    name,
+                    format!("0x{:08X}", range.start),
+                    format!("0x{:08X}", range.end - 1),
+                    format_size(range.end - range.start),
+                    pages,
+                ];
+
+                for text in cells {
+                    row.col(|ui| {
+                        let color = text_color.unwrap_or(ui.visuals().text_color());
+                        ui.colored_label(color, text);
+                    });
+                }
+
+                row.col(|ui| {
+                    if is_gap {
+                        return;
+                    }
+
+                    let (rect, response) = allocate_strip(ui, ROW_HEIGHT - 6.0);
+                    paint_range(ui, rect, &span, &range, USED_COLOR);
+
+                    for overlap in &overlaps {
+                        paint_range(ui, rect, &span, &overlap.range, OVERLAP_COLOR);
+                    }
+
+                    if !overlaps.is_empty() {
+                        response.on_hover_ui(|ui| {
+                            for overlap in &overlaps {
+                                ui.label(format!(
+                                    "Records #{:06} and #{:06} both write 0x{:08X} - 0x{:08X}{}",
+                                    overlap.records.0,
+                                    overlap.records.1,
+                                    overlap.range.start,
+                                    overlap.range.end - 1,
+                                    if overlap.conflicting {
+                                        " with different data"
+                                    } else {
+                                        ""
+                                    }
+                                ));
+                            }
+                        });
+                    }
+                });
+            });
+        });
+}
+
+pub(super) fn layout_tab(memory: &MemoryMap, meta: &mut LayoutTabMeta, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        if memory.is_empty() {
+            ui.label("No data");
+            return;
+        }
+
+        ui.label(format!(
+            "Used: {} bytes in {} region(s)",
+            memory.len(),
+            memory.segments().len()
+        ));
+
+        if let (Some(min), Some(max)) = (memory.min_address(), memory.max_address()) {
+            ui.add_space(5.0);
+            ui.label(format!("Range: 0x{:08X} - 0x{:08X}", min, max));
+        }
+
+        if !meta.overlaps.is_empty() {
+            ui.add_space(5.0);
+            ui.colored_label(
+                OVERLAP_COLOR,
+                format!("Overlapping records: {}", meta.overlaps.len()),
+            );
+        }
+
+        ui.add_space(5.0);
+        ui.label("Page size:");
+
+        ComboBox::from_id_source("page_size_box")
+            .selected_text(format_size(meta.page_size as u64))
+            .show_ui(ui, |ui| {
+                for size in PAGE_SIZES {
+                    ui.selectable_value(&mut meta.page_size, size, format_size(size as u64));
+                }
+            });
+    });
+
+    page_strip(memory, meta, ui);
+
+    ui.add_space(5.0);
+
+    region_table(memory, meta, ui);
+}
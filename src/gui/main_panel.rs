@@ -1,6 +1,6 @@
 use core::panic;
 use eframe::{
-    egui::{ComboBox, Context, Ui},
+    egui::{Align, Button, Color32, ComboBox, Context, Ui},
     Frame,
 };
 use egui_extras::{Column, TableBuilder, TableRow};
@@ -8,17 +8,27 @@ use itertools::Itertools;
 use std::hash::Hash;
 use strum::IntoEnumIterator;
 
-use crate::record::{
-    file::{IHexFile, StartAddr},
-    DataRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord, IHexRecord,
-    StartLinearAddressRecord, StartSegmentAddressRecord,
+use cpr_ihex::{
+    memory::MemoryMap,
+    record::{
+        file::{IHexDiagnostic, IHexFile, StartAddr},
+        raw::RawIHexRecord,
+        DataRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord, IHexRecord,
+        StartLinearAddressRecord, StartSegmentAddressRecord,
+    },
 };
 
 use super::{
-    DataDisplayMeta, DataDisplayMode, DataTabMeta, IHexRecordDisplayMeta, MainPanel, MainPanelTab,
+    compare_tab::compare_tab,
+    edit::{address_edit_ui, data_edit_ui, DataEditState, RecordEdit},
+    goto::goto_bar,
+    layout_tab::layout_tab,
+    memory_tab::memory_tab,
+    AddressDisplayMeta, DataDisplayMeta, DataDisplayMode, DataTabMeta, DataTableRow,
+    IHexRecordDisplayMeta, MainPanel, MainPanelTab,
 };
 
-fn display_mode_combobox(id: impl Hash, curr: &mut DataDisplayMode, ui: &mut Ui) {
+pub(super) fn display_mode_combobox(id: impl Hash, curr: &mut DataDisplayMode, ui: &mut Ui) {
     ComboBox::from_id_source(id)
         .selected_text(curr.as_str())
         .show_ui(ui, |ui| {
@@ -68,10 +78,28 @@ fn numvec_as_utf16_string(data: &[u8], endian: Endian) -> String {
     format!("[{}]", String::from_utf16_lossy(&utf16_data))
 }
 
-fn display_data(i: usize, meta: &mut DataDisplayMeta, record: &DataRecord, ui: &mut Ui) {
+fn display_data(
+    i: usize,
+    meta: &mut DataDisplayMeta,
+    record: &DataRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    if meta.edit.is_some() {
+        return data_edit_ui(i, &mut meta.edit, record, ui);
+    }
+
     ui.horizontal(|ui| {
         let effective_address = record.calc_effective_address();
 
+        if meta.modified {
+            ui.colored_label(Color32::YELLOW, "*")
+                .on_hover_text("This record was modified");
+        }
+
+        if ui.small_button("Edit").clicked() {
+            meta.edit = Some(DataEditState::new(record));
+        }
+
         ui.label(format!(
             "Address: 0x{:x} (0x{:x})",
             effective_address, record.naive_address
@@ -80,6 +108,11 @@ fn display_data(i: usize, meta: &mut DataDisplayMeta, record: &DataRecord, ui: &
         ui.label(format!("{} bytes", record.data.len()));
         ui.add_space(5.0);
 
+        if let Ok(raw) = RawIHexRecord::try_from(&IHexRecord::Data(record.clone())) {
+            ui.label(format!("Checksum: 0x{:02X}", raw.checksum));
+        }
+        ui.add_space(5.0);
+
         display_mode_combobox(i, &mut meta.displaymode, ui);
 
         let data_str = match meta.displaymode {
@@ -92,10 +125,38 @@ fn display_data(i: usize, meta: &mut DataDisplayMeta, record: &DataRecord, ui: &
 
         ui.label(data_str);
     });
+
+    None
+}
+
+fn address_edit_button(meta: &mut AddressDisplayMeta, base: u16, ui: &mut Ui) {
+    if meta.modified {
+        ui.colored_label(Color32::YELLOW, "*")
+            .on_hover_text("This record was modified");
+    }
+
+    if ui.small_button("Edit").clicked() {
+        meta.edit = Some(format!("0x{:04X}", base));
+    }
 }
 
-fn display_extended_segment_address(record: &ExtendedSegmentAddressRecord, ui: &mut Ui) {
-    ui.label(format!("Base Address: 0x{:x}", record.segment_base));
+fn display_extended_segment_address(
+    i: usize,
+    meta: &mut AddressDisplayMeta,
+    record: &ExtendedSegmentAddressRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    if meta.edit.is_some() {
+        let record = IHexRecord::ExtendedSegmentAddress(record.clone());
+        return address_edit_ui(i, meta, &record, ui);
+    }
+
+    ui.horizontal(|ui| {
+        address_edit_button(meta, (record.segment_base >> 4) as u16, ui);
+        ui.label(format!("Base Address: 0x{:x}", record.segment_base));
+    });
+
+    None
 }
 
 fn display_start_segment_address(record: &StartSegmentAddressRecord, ui: &mut Ui) {
@@ -105,19 +166,35 @@ fn display_start_segment_address(record: &StartSegmentAddressRecord, ui: &mut Ui
     ));
 }
 
-fn display_extended_linear_address(record: &ExtendedLinearAddressRecord, ui: &mut Ui) {
-    let base_32: u32 = (record.address_base as u32) << 16;
-    ui.label(format!("Linear Base Address: 0x{:x}", base_32));
+fn display_extended_linear_address(
+    i: usize,
+    meta: &mut AddressDisplayMeta,
+    record: &ExtendedLinearAddressRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    if meta.edit.is_some() {
+        let record = IHexRecord::ExtendedLinearAddress(record.clone());
+        return address_edit_ui(i, meta, &record, ui);
+    }
+
+    ui.horizontal(|ui| {
+        address_edit_button(meta, record.address_base, ui);
+
+        let base_32: u32 = (record.address_base as u32) << 16;
+        ui.label(format!("Linear Base Address: 0x{:x}", base_32));
+    });
+
+    None
 }
 
 fn display_start_linear_address(record: &StartLinearAddressRecord, ui: &mut Ui) {
     ui.label(format!("0x{:x}", record.entry_point));
 }
 
-const fn record_type_name(record: &IHexRecord) -> &'static str {
+pub(super) const fn record_type_name(record: &IHexRecord) -> &'static str {
     match record {
         IHexRecord::Data(_) => "Data",
-        IHexRecord::EndOfFile => "End of File",
+        IHexRecord::EndOfFile(_) => "End of File",
         IHexRecord::ExtendedSegmentAddress(_) => "Extended Segment Address",
         IHexRecord::StartSegmentAddress(_) => "Start Segment Address",
         IHexRecord::ExtendedLinearAddress(_) => "Extended Linear Address",
@@ -134,14 +211,87 @@ macro_rules! get_variant_or_panic {
     };
 }
 
+fn record_actions_menu(
+    i: usize,
+    num_records: usize,
+    record: &IHexRecord,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    let mut edit = None;
+
+    ui.menu_button("...", |ui| {
+        let next_offset = match record {
+            IHexRecord::Data(data) => data.naive_address.wrapping_add(data.data.len() as u16),
+            _ => 0,
+        };
+
+        let inserts = [
+            (
+                "Insert data record below",
+                IHexRecord::Data(DataRecord::new(next_offset, Vec::new())),
+            ),
+            (
+                "Insert extended linear address below",
+                IHexRecord::ExtendedLinearAddress(ExtendedLinearAddressRecord { address_base: 0 }),
+            ),
+            (
+                "Insert extended segment address below",
+                IHexRecord::ExtendedSegmentAddress(ExtendedSegmentAddressRecord {
+                    segment_base: 0,
+                }),
+            ),
+        ];
+
+        for (label, new_record) in inserts {
+            if ui.button(label).clicked() {
+                edit = Some(RecordEdit::Insert {
+                    index: i + 1,
+                    record: new_record,
+                });
+            }
+        }
+
+        ui.separator();
+
+        if ui.add_enabled(i > 0, Button::new("Move up")).clicked() {
+            edit = Some(RecordEdit::Move { from: i, to: i - 1 });
+        }
+
+        if ui
+            .add_enabled(i + 1 < num_records, Button::new("Move down"))
+            .clicked()
+        {
+            edit = Some(RecordEdit::Move { from: i, to: i + 1 });
+        }
+
+        ui.separator();
+
+        if ui.button("Delete").clicked() {
+            edit = Some(RecordEdit::Remove { index: i });
+        }
+
+        if edit.is_some() {
+            ui.close_menu();
+        }
+    });
+
+    edit
+}
+
 fn display_record(
     meta: &mut IHexRecordDisplayMeta,
     i: usize,
+    num_records: usize,
     record: &IHexRecord,
     row: &mut TableRow,
-) {
+) -> Option<RecordEdit> {
+    let mut edit = None;
+
     row.col(|ui| {
-        ui.label(format!("#{:06}", i));
+        ui.horizontal(|ui| {
+            ui.label(format!("#{:06}", i));
+            edit = record_actions_menu(i, num_records, record, ui);
+        });
     });
 
     row.col(|ui| {
@@ -151,14 +301,34 @@ fn display_record(
     row.col(|ui| match record {
         IHexRecord::Data(data) => {
             let meta_for_rec = get_variant_or_panic!(meta, IHexRecordDisplayMeta::Data(data), data);
-            display_data(i, meta_for_rec, data, ui)
+            edit = edit.take().or(display_data(i, meta_for_rec, data, ui))
+        }
+        IHexRecord::EndOfFile(_) => (),
+        IHexRecord::ExtendedSegmentAddress(esa) => {
+            let meta_for_rec = get_variant_or_panic!(
+                meta,
+                IHexRecordDisplayMeta::ExtendedSegmentAddress(meta),
+                meta
+            );
+            edit = edit
+                .take()
+                .or(display_extended_segment_address(i, meta_for_rec, esa, ui))
         }
-        IHexRecord::EndOfFile => (),
-        IHexRecord::ExtendedSegmentAddress(esa) => display_extended_segment_address(esa, ui),
         IHexRecord::StartSegmentAddress(ssa) => display_start_segment_address(ssa, ui),
-        IHexRecord::ExtendedLinearAddress(ela) => display_extended_linear_address(ela, ui),
+        IHexRecord::ExtendedLinearAddress(ela) => {
+            let meta_for_rec = get_variant_or_panic!(
+                meta,
+                IHexRecordDisplayMeta::ExtendedLinearAddress(meta),
+                meta
+            );
+            edit = edit
+                .take()
+                .or(display_extended_linear_address(i, meta_for_rec, ela, ui))
+        }
         IHexRecord::StartLinearAddress(sla) => display_start_linear_address(sla, ui),
     });
+
+    edit
 }
 
 const TABLE_ROW_HEIGHT: f32 = 20.0;
@@ -166,7 +336,7 @@ const TABLE_ROW_HEIGHT: f32 = 20.0;
 fn get_record_height(record: &IHexRecord) -> f32 {
     match record {
         IHexRecord::Data(_) => TABLE_ROW_HEIGHT,
-        IHexRecord::EndOfFile => TABLE_ROW_HEIGHT,
+        IHexRecord::EndOfFile(_) => TABLE_ROW_HEIGHT,
         IHexRecord::ExtendedSegmentAddress(_) => TABLE_ROW_HEIGHT * 3.0,
         IHexRecord::StartSegmentAddress(_) => TABLE_ROW_HEIGHT * 3.0,
         IHexRecord::ExtendedLinearAddress(_) => TABLE_ROW_HEIGHT * 3.0,
@@ -174,7 +344,30 @@ fn get_record_height(record: &IHexRecord) -> f32 {
     }
 }
 
-fn data_tab(file: &IHexFile, meta: &mut DataTabMeta, ui: &mut Ui) {
+fn display_broken_record(diagnostic: &IHexDiagnostic, row: &mut TableRow) {
+    row.col(|ui| {
+        ui.colored_label(Color32::RED, format!("Line {}", diagnostic.error.line));
+    });
+
+    row.col(|ui| {
+        ui.colored_label(Color32::RED, "Invalid");
+    });
+
+    row.col(|ui| {
+        ui.colored_label(Color32::RED, diagnostic.error.to_string());
+    });
+}
+
+fn data_tab(
+    file: &IHexFile,
+    diagnostics: &[IHexDiagnostic],
+    memory: &MemoryMap,
+    meta: &mut DataTabMeta,
+    selected_record: Option<usize>,
+    ui: &mut Ui,
+) -> Option<RecordEdit> {
+    let mut edit = None;
+
     ui.spacing_mut().item_spacing.y += 3.0;
 
     ui.horizontal(|ui| {
@@ -198,6 +391,18 @@ fn data_tab(file: &IHexFile, meta: &mut DataTabMeta, ui: &mut Ui) {
 
         ui.label(format!("File type: {}", file.filetype()));
 
+        ui.add_space(5.0);
+
+        if memory.is_empty() {
+            ui.label("No data");
+        } else {
+            ui.label(format!(
+                "Data: {} bytes in {} region(s)",
+                memory.len(),
+                memory.segments().len()
+            ));
+        }
+
         if let Some(start_addr) = file.start_address() {
             ui.add_space(5.0);
 
@@ -213,15 +418,35 @@ fn data_tab(file: &IHexFile, meta: &mut DataTabMeta, ui: &mut Ui) {
                 }
             }
         }
+
+        if !diagnostics.is_empty() {
+            ui.add_space(5.0);
+            ui.colored_label(
+                Color32::RED,
+                format!("Invalid records: {}", diagnostics.len()),
+            );
+        }
     });
 
     let max_scroll_height = ui.available_height() - TABLE_ROW_HEIGHT;
 
-    TableBuilder::new(ui)
+    let mut table = TableBuilder::new(ui)
         .striped(true)
         .resizable(true)
         .auto_shrink([false, false])
-        .max_scroll_height(max_scroll_height)
+        .max_scroll_height(max_scroll_height);
+
+    let scroll_to_row = meta.scroll_to_record.take().and_then(|record| {
+        meta.rows
+            .iter()
+            .position(|row| matches!(row, DataTableRow::Record(i) if *i == record))
+    });
+
+    if let Some(row) = scroll_to_row {
+        table = table.scroll_to_row(row, Some(Align::Center));
+    }
+
+    table
         .column(Column::auto().at_least(75.0))
         .column(Column::auto().at_least(60.0))
         .column(Column::remainder())
@@ -237,23 +462,85 @@ fn data_tab(file: &IHexFile, meta: &mut DataTabMeta, ui: &mut Ui) {
             });
         })
         .body(|body| {
-            let height_iter = file.records.iter().map(get_record_height);
+            let height_iter = meta.rows.iter().map(|row| match row {
+                DataTableRow::Record(i) => get_record_height(&file.records[*i]),
+                DataTableRow::Broken(_) => TABLE_ROW_HEIGHT,
+            });
+
+            body.heterogeneous_rows(height_iter, |mut row| match meta.rows[row.index()] {
+                DataTableRow::Record(i) => {
+                    let record = file.records.get(i).unwrap();
+                    let record_meta = meta.record_meta.get_mut(i).unwrap();
 
-            body.heterogeneous_rows(height_iter, |mut row| {
-                let record = file.records.get(row.index()).unwrap();
-                let record_meta = meta.record_meta.get_mut(row.index()).unwrap();
+                    assert!(record_meta.check_matches(record));
 
-                assert!(record_meta.check_matches(record));
+                    row.set_selected(selected_record == Some(i));
 
-                display_record(record_meta, row.index(), record, &mut row);
+                    if let Some(record_edit) =
+                        display_record(record_meta, i, file.records.len(), record, &mut row)
+                    {
+                        edit = Some(record_edit);
+                    }
+                }
+                DataTableRow::Broken(i) => display_broken_record(&diagnostics[i], &mut row),
             });
         });
+
+    edit
 }
 
-pub fn gui(mainpanel: &mut MainPanel, _ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+pub fn gui(mainpanel: &mut MainPanel, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        for tab in MainPanelTab::iter() {
+            ui.selectable_value(&mut mainpanel.tab, tab, tab.as_str());
+        }
+
+        ui.separator();
+
+        goto_bar(mainpanel, ctx, ui);
+    });
+
+    ui.separator();
+
     let hexfile = &mainpanel.file;
+    let diagnostics = &mainpanel.diagnostics;
+    let memory = &mainpanel.memory;
+
+    let tab = mainpanel.tab;
+    let meta = &mut mainpanel.meta;
+
+    // Each tab gets its own id, so the tables don't share their scroll state
+    let (edit, show_record) = ui
+        .push_id(tab.as_str(), |ui| match tab {
+            MainPanelTab::Data => (
+                data_tab(
+                    hexfile,
+                    diagnostics,
+                    memory,
+                    &mut meta.data,
+                    meta.selected_record,
+                    ui,
+                ),
+                None,
+            ),
+            MainPanelTab::Memory => (None, memory_tab(hexfile, memory, meta, ui)),
+            MainPanelTab::MemoryMap => {
+                layout_tab(memory, &mut meta.layout, ui);
+                (None, None)
+            }
+            MainPanelTab::Compare => {
+                compare_tab(hexfile, memory, &mut meta.compare, ui);
+                (None, None)
+            }
+        })
+        .inner;
+
+    if let Some(record) = show_record {
+        mainpanel.meta.data.scroll_to_record = Some(record);
+        mainpanel.tab = MainPanelTab::Data;
+    }
 
-    match &mut mainpanel.tab {
-        MainPanelTab::Data => data_tab(hexfile, &mut mainpanel.meta.data, ui),
+    if let Some(edit) = edit {
+        mainpanel.apply_edit(edit);
     }
 }
@@ -0,0 +1,301 @@
+use std::ops::Range;
+
+use eframe::egui::{Align, Color32, Label, RichText, Sense, Ui};
+use egui_extras::{Column, TableBuilder};
+
+use cpr_ihex::{
+    memory::MemoryMap,
+    record::{file::IHexFile, IHexRecord},
+};
+
+use super::MainPanelMeta;
+
+pub(super) const BYTES_PER_ROW: u32 = 16;
+pub(super) const ROW_HEIGHT: f32 = 18.0;
+
+/// A row in the memory grid
+#[derive(Clone)]
+pub(super) enum MemoryRow {
+    /// The 16 bytes starting at the given (aligned) address
+    Bytes(u32),
+    /// A range of unmapped addresses between two segments
+    Gap(Range<u64>),
+}
+
+impl MemoryRow {
+    /// Builds the rows for all segments, collapsing the rows between segments into a single gap row
+    pub(super) fn build_all(memory: &MemoryMap) -> Vec<Self> {
+        let mut rows = Vec::new();
+        // The address right after the last row that was added, and the end of the previous segment
+        let mut prev: Option<(u64, u64)> = None;
+
+        for seg in memory.segments() {
+            let first_row = seg.start as u64 / BYTES_PER_ROW as u64 * BYTES_PER_ROW as u64;
+            let last_row = (seg.end() - 1) / BYTES_PER_ROW as u64 * BYTES_PER_ROW as u64;
+
+            let first_new_row = match prev {
+                // The segment starts in a row that was already added
+                Some((rows_end, _)) if first_row < rows_end => rows_end,
+                Some((rows_end, prev_end)) => {
+                    if first_row > rows_end {
+                        rows.push(MemoryRow::Gap(prev_end..seg.start as u64));
+                    }
+
+                    first_row
+                }
+                None => first_row,
+            };
+
+            rows.extend(
+                (first_new_row..=last_row)
+                    .step_by(BYTES_PER_ROW as usize)
+                    .map(|address| MemoryRow::Bytes(address as u32)),
+            );
+
+            prev = Some((last_row + BYTES_PER_ROW as u64, seg.end()));
+        }
+
+        rows
+    }
+
+    /// The index of the row containing `address`, or the gap row it falls in
+    pub(super) fn find(rows: &[Self], address: u32) -> Option<usize> {
+        let row = rows.partition_point(|row| match row {
+            MemoryRow::Bytes(start) => *start <= address,
+            MemoryRow::Gap(gap) => gap.start <= address as u64,
+        });
+
+        row.checked_sub(1)
+    }
+}
+
+pub(super) struct MemoryTabMeta {
+    rows: Vec<MemoryRow>,
+    /// The byte that was clicked last, or the start of the last search match
+    pub(super) selected_address: Option<u32>,
+    /// The amount of selected bytes starting at `selected_address`
+    pub(super) selected_length: u32,
+    /// The row to scroll to in the next frame
+    scroll_to_row: Option<usize>,
+}
+
+impl MemoryTabMeta {
+    pub(super) fn new(memory: &MemoryMap) -> Self {
+        MemoryTabMeta {
+            rows: MemoryRow::build_all(memory),
+            selected_address: None,
+            selected_length: 1,
+            scroll_to_row: None,
+        }
+    }
+
+    pub(super) fn rebuild(&mut self, memory: &MemoryMap) {
+        self.rows = MemoryRow::build_all(memory);
+    }
+
+    /// Scrolls to the row containing `address` in the next frame
+    pub(super) fn scroll_to_address(&mut self, address: u32) {
+        self.scroll_to_row = MemoryRow::find(&self.rows, address);
+    }
+}
+
+/// The effective address range covered by a data record
+fn record_range(record: &IHexRecord) -> Option<Range<u64>> {
+    match record {
+        IHexRecord::Data(data) => {
+            let start = data.calc_effective_address() as u64;
+            Some(start..start + data.data.len() as u64)
+        }
+        _ => None,
+    }
+}
+
+fn byte_text(byte: Option<u8>, ascii: bool) -> String {
+    match (byte, ascii) {
+        (Some(byte), false) => format!("{:02X}", byte),
+        (Some(byte), true) if byte.is_ascii_graphic() || byte == b' ' => (byte as char).to_string(),
+        (Some(_), true) => ".".to_string(),
+        (None, false) => "  ".to_string(),
+        (None, true) => " ".to_string(),
+    }
+}
+
+/// Shows a single hex or ASCII cell. Returns true if it was clicked.
+fn byte_cell(
+    text: String,
+    selected: bool,
+    in_selected_record: bool,
+    mapped: bool,
+    ui: &mut Ui,
+) -> bool {
+    let mut text = RichText::new(text).monospace();
+
+    if selected {
+        text = text.background_color(ui.visuals().selection.bg_fill);
+    } else if in_selected_record {
+        text = text.background_color(ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+    }
+
+    let mut label = Label::new(text);
+
+    if mapped {
+        label = label.sense(Sense::click());
+    }
+
+    ui.add(label).clicked()
+}
+
+/// Shows the memory grid. Returns the record to show in the data tab, if requested.
+pub(super) fn memory_tab(
+    file: &IHexFile,
+    memory: &MemoryMap,
+    meta: &mut MainPanelMeta,
+    ui: &mut Ui,
+) -> Option<usize> {
+    let selection = meta
+        .memory
+        .selected_address
+        .map(|address| address as u64..address as u64 + meta.memory.selected_length.max(1) as u64);
+
+    let selected_range = meta
+        .selected_record
+        .and_then(|i| file.records.get(i))
+        .and_then(record_range);
+
+    let mut clicked = None;
+    let mut show_record = None;
+
+    ui.horizontal(|ui| {
+        if memory.is_empty() {
+            ui.label("No data");
+        } else {
+            ui.label(format!(
+                "Data: {} bytes in {} region(s)",
+                memory.len(),
+                memory.segments().len()
+            ));
+        }
+
+        let Some(address) = meta.memory.selected_address else {
+            return;
+        };
+
+        ui.add_space(5.0);
+
+        match memory.get(address) {
+            Some(byte) => ui.label(format!("Selected: 0x{:08X} = 0x{:02X}", address, byte)),
+            None => ui.label(format!("Selected: 0x{:08X} (unmapped)", address)),
+        };
+
+        if let Some(record) = meta.selected_record {
+            ui.add_space(5.0);
+            ui.label(format!("From record #{:06}", record));
+
+            if ui.small_button("Show record").clicked() {
+                show_record = Some(record);
+            }
+        }
+    });
+
+    let max_scroll_height = ui.available_height() - ROW_HEIGHT;
+
+    let mut table = TableBuilder::new(ui)
+        .striped(true)
+        .auto_shrink([false, false])
+        .max_scroll_height(max_scroll_height)
+        .column(Column::auto().at_least(90.0))
+        .column(Column::auto().at_least(420.0))
+        .column(Column::remainder());
+
+    if let Some(row) = meta.memory.scroll_to_row.take() {
+        table = table.scroll_to_row(row, Some(Align::Center));
+    }
+
+    table
+        .header(ROW_HEIGHT, |mut header| {
+            header.col(|ui| {
+                ui.heading("Address");
+            });
+            header.col(|ui| {
+                ui.heading("Bytes");
+            });
+            header.col(|ui| {
+                ui.heading("ASCII");
+            });
+        })
+        .body(|body| {
+            body.rows(ROW_HEIGHT, meta.memory.rows.len(), |mut row| {
+                let row_address = match &meta.memory.rows[row.index()] {
+                    MemoryRow::Bytes(address) => *address,
+                    MemoryRow::Gap(gap) => {
+                        row.col(|_| {});
+                        row.col(|ui| {
+                            ui.colored_label(
+                                Color32::GRAY,
+                                format!(
+                                    "Gap: 0x{:08X} - 0x{:08X} ({} bytes unmapped)",
+                                    gap.start,
+                                    gap.end - 1,
+                                    gap.end - gap.start
+                                ),
+                            );
+                        });
+                        row.col(|_| {});
+                        return;
+                    }
+                };
+
+                let cells: Vec<_> = (0..BYTES_PER_ROW)
+                    .map(|offset| {
+                        let address = row_address + offset;
+                        let in_record = selected_range
+                            .as_ref()
+                            .is_some_and(|range| range.contains(&(address as u64)));
+
+                        (
+                            address,
+                            memory.get(address),
+                            selection
+                                .as_ref()
+                                .is_some_and(|range| range.contains(&(address as u64))),
+                            in_record,
+                        )
+                    })
+                    .collect();
+
+                row.col(|ui| {
+                    ui.monospace(format!("{:08X}", row_address));
+                });
+
+                for ascii in [false, true] {
+                    row.col(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = if ascii { 0.0 } else { 4.0 };
+
+                            for (i, (address, byte, selected, in_record)) in
+                                cells.iter().enumerate()
+                            {
+                                if !ascii && i == BYTES_PER_ROW as usize / 2 {
+                                    ui.add_space(6.0);
+                                }
+
+                                let text = byte_text(*byte, ascii);
+
+                                if byte_cell(text, *selected, *in_record, byte.is_some(), ui) {
+                                    clicked = Some(*address);
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+    if let Some(address) = clicked {
+        meta.memory.selected_address = Some(address);
+        meta.memory.selected_length = 1;
+        meta.selected_record = file.record_at_address(address);
+    }
+
+    show_record
+}
@@ -0,0 +1,213 @@
+use eframe::egui::{menu, Context, Key, KeyboardShortcut, Modifiers, TopBottomPanel};
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+
+use super::{
+    checksum_panel, compare_tab,
+    history::{self, REDO_SHORTCUT, UNDO_SHORTCUT},
+    open_file,
+    search::{self, SEARCH_SHORTCUT},
+    Gui, MainPanelTab,
+};
+
+const OPEN_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::O);
+const SAVE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+const SAVE_AS_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::S);
+
+enum MenuAction {
+    Open,
+    Save,
+    SaveAs,
+    Compare,
+    Undo,
+    Redo,
+    Search,
+}
+
+fn consume_shortcuts(ctx: &Context) -> Option<MenuAction> {
+    ctx.input_mut(|input| {
+        // Most specific shortcuts first, see `InputState::consume_shortcut`
+        if input.consume_shortcut(&SAVE_AS_SHORTCUT) {
+            Some(MenuAction::SaveAs)
+        } else if input.consume_shortcut(&SAVE_SHORTCUT) {
+            Some(MenuAction::Save)
+        } else if input.consume_shortcut(&OPEN_SHORTCUT) {
+            Some(MenuAction::Open)
+        } else if input.consume_shortcut(&SEARCH_SHORTCUT) {
+            Some(MenuAction::Search)
+        } else {
+            None
+        }
+    })
+}
+
+fn open(gui: &mut Gui) {
+    if let Gui::MainPanel(panel) = gui {
+        if !panel.confirm_discard() {
+            return;
+        }
+    }
+
+    let Some(path) = open_file::pick_file() else {
+        return;
+    };
+
+    match open_file::open_path(&path) {
+        Ok(opened) => gui.file_opened(opened, Some(path)),
+        Err(e) => {
+            MessageDialog::new()
+                .set_level(MessageLevel::Error)
+                .set_title("Could not open file")
+                .set_description(e)
+                .set_buttons(MessageButtons::Ok)
+                .show();
+        }
+    }
+}
+
+/// Shows the menu bar of the main panel, and handles its keyboard shortcuts
+pub(super) fn menu_bar(gui: &mut Gui, ctx: &Context) {
+    let Gui::MainPanel(panel) = gui else {
+        return;
+    };
+
+    panel.handle_history_shortcuts(ctx);
+
+    let mut action = consume_shortcuts(ctx);
+
+    TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+        menu::bar(ui, |ui| {
+            ui.menu_button("File", |ui| {
+                if ui
+                    .add(
+                        eframe::egui::Button::new("Open...")
+                            .shortcut_text(ctx.format_shortcut(&OPEN_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::Open);
+                    ui.close_menu();
+                }
+
+                if ui
+                    .add(
+                        eframe::egui::Button::new("Save")
+                            .shortcut_text(ctx.format_shortcut(&SAVE_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::Save);
+                    ui.close_menu();
+                }
+
+                if ui
+                    .add(
+                        eframe::egui::Button::new("Save As...")
+                            .shortcut_text(ctx.format_shortcut(&SAVE_AS_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::SaveAs);
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                if ui.button("Compare With...").clicked() {
+                    action = Some(MenuAction::Compare);
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("Edit", |ui| {
+                if ui
+                    .add_enabled(
+                        panel.history.can_undo(),
+                        eframe::egui::Button::new("Undo")
+                            .shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::Undo);
+                    ui.close_menu();
+                }
+
+                if ui
+                    .add_enabled(
+                        panel.history.can_redo(),
+                        eframe::egui::Button::new("Redo")
+                            .shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::Redo);
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("View", |ui| {
+                if ui.checkbox(&mut panel.show_history, "History").clicked() {
+                    ui.close_menu();
+                }
+
+                if ui.checkbox(&mut panel.show_search, "Search").clicked() {
+                    ui.close_menu();
+                }
+
+                if ui.checkbox(&mut panel.show_checksum, "Checksum").clicked() {
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("Search", |ui| {
+                if ui
+                    .add(
+                        eframe::egui::Button::new("Find...")
+                            .shortcut_text(ctx.format_shortcut(&SEARCH_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    action = Some(MenuAction::Search);
+                    ui.close_menu();
+                }
+            });
+        });
+    });
+
+    if let Some(MenuAction::Search) = action {
+        panel.show_search = true;
+        panel.meta.search.focus();
+    }
+
+    if panel.show_history {
+        history::history_panel(panel, ctx);
+    }
+
+    if panel.show_search {
+        search::search_panel(panel, ctx);
+    }
+
+    if panel.show_checksum {
+        checksum_panel::checksum_panel(panel, ctx);
+    }
+
+    match action {
+        Some(MenuAction::Open) => open(gui),
+        Some(MenuAction::Save) => {
+            panel.save();
+        }
+        Some(MenuAction::SaveAs) => {
+            panel.save_as();
+        }
+        Some(MenuAction::Compare) => {
+            if let Some(compare) = compare_tab::open_compare(&panel.file, &panel.memory) {
+                panel.meta.compare = Some(compare);
+                panel.tab = MainPanelTab::Compare;
+            }
+        }
+        Some(MenuAction::Undo) => panel.undo(),
+        Some(MenuAction::Redo) => panel.redo(),
+        Some(MenuAction::Search) => {}
+        None => {}
+    }
+}
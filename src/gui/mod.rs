@@ -1,13 +1,33 @@
+mod checksum_panel;
+mod compare_tab;
+mod edit;
+mod goto;
+mod history;
+mod layout_tab;
 mod main_panel;
+mod memory_tab;
+mod menu;
 mod open_file;
+mod save;
+mod search;
+
+use std::path::PathBuf;
 
 use eframe::{
-    egui::{CentralPanel, Context},
+    egui::{CentralPanel, Context, ViewportCommand},
     Frame,
 };
+use history::History;
 use strum::EnumIter;
 
-use crate::record::{file::IHexFile, IHexRecord};
+use cpr_ihex::{
+    memory::MemoryMap,
+    record::{
+        file::{IHexDiagnostic, IHexFile},
+        IHexRecord,
+    },
+    search::TextEncoding,
+};
 
 #[derive(EnumIter, PartialEq, Eq, Clone)]
 enum DataDisplayMode {
@@ -19,6 +39,17 @@ enum DataDisplayMode {
 }
 
 impl DataDisplayMode {
+    /// The text encoding of the mode, or `None` for the raw bytes mode
+    const fn text_encoding(&self) -> Option<TextEncoding> {
+        match self {
+            DataDisplayMode::Bytes => None,
+            DataDisplayMode::Chars => Some(TextEncoding::Ascii),
+            DataDisplayMode::Utf8 => Some(TextEncoding::Utf8),
+            DataDisplayMode::Utf16LE => Some(TextEncoding::Utf16Le),
+            DataDisplayMode::Utf16BE => Some(TextEncoding::Utf16Be),
+        }
+    }
+
     const fn as_str(&self) -> &'static str {
         match self {
             DataDisplayMode::Bytes => "Bytes",
@@ -32,14 +63,22 @@ impl DataDisplayMode {
 
 struct DataDisplayMeta {
     displaymode: DataDisplayMode,
+    edit: Option<edit::DataEditState>,
+    modified: bool,
+}
+
+/// Metadata for the extended address records, whose base address can be edited
+struct AddressDisplayMeta {
+    edit: Option<String>,
+    modified: bool,
 }
 
 enum IHexRecordDisplayMeta {
     Data(DataDisplayMeta),
     EndOfFile,
-    ExtendedSegmentAddress,
+    ExtendedSegmentAddress(AddressDisplayMeta),
     StartSegmentAddress,
-    ExtendedLinearAddress,
+    ExtendedLinearAddress(AddressDisplayMeta),
     StartLinearAddress,
 }
 
@@ -47,15 +86,15 @@ impl IHexRecordDisplayMeta {
     fn check_matches(&self, record: &IHexRecord) -> bool {
         match record {
             IHexRecord::Data(_) => matches!(self, IHexRecordDisplayMeta::Data { .. }),
-            IHexRecord::EndOfFile => matches!(self, IHexRecordDisplayMeta::EndOfFile),
+            IHexRecord::EndOfFile(_) => matches!(self, IHexRecordDisplayMeta::EndOfFile),
             IHexRecord::ExtendedSegmentAddress(_) => {
-                matches!(self, IHexRecordDisplayMeta::ExtendedSegmentAddress)
+                matches!(self, IHexRecordDisplayMeta::ExtendedSegmentAddress(_))
             }
             IHexRecord::StartSegmentAddress(_) => {
                 matches!(self, IHexRecordDisplayMeta::StartSegmentAddress)
             }
             IHexRecord::ExtendedLinearAddress(_) => {
-                matches!(self, IHexRecordDisplayMeta::ExtendedLinearAddress)
+                matches!(self, IHexRecordDisplayMeta::ExtendedLinearAddress(_))
             }
             IHexRecord::StartLinearAddress(_) => {
                 matches!(self, IHexRecordDisplayMeta::StartLinearAddress)
@@ -67,74 +106,233 @@ impl IHexRecordDisplayMeta {
         match record {
             IHexRecord::Data(_) => IHexRecordDisplayMeta::Data(DataDisplayMeta {
                 displaymode: DataDisplayMode::Bytes,
+                edit: None,
+                modified: false,
             }),
-            IHexRecord::EndOfFile => IHexRecordDisplayMeta::EndOfFile,
-            IHexRecord::ExtendedSegmentAddress(_) => IHexRecordDisplayMeta::ExtendedSegmentAddress,
+            IHexRecord::EndOfFile(_) => IHexRecordDisplayMeta::EndOfFile,
+            IHexRecord::ExtendedSegmentAddress(_) => {
+                IHexRecordDisplayMeta::ExtendedSegmentAddress(AddressDisplayMeta {
+                    edit: None,
+                    modified: false,
+                })
+            }
             IHexRecord::StartSegmentAddress(_) => IHexRecordDisplayMeta::StartSegmentAddress,
-            IHexRecord::ExtendedLinearAddress(_) => IHexRecordDisplayMeta::ExtendedLinearAddress,
+            IHexRecord::ExtendedLinearAddress(_) => {
+                IHexRecordDisplayMeta::ExtendedLinearAddress(AddressDisplayMeta {
+                    edit: None,
+                    modified: false,
+                })
+            }
             IHexRecord::StartLinearAddress(_) => IHexRecordDisplayMeta::StartLinearAddress,
         }
     }
+
+    fn modified(&self) -> bool {
+        match self {
+            IHexRecordDisplayMeta::Data(meta) => meta.modified,
+            IHexRecordDisplayMeta::ExtendedSegmentAddress(meta)
+            | IHexRecordDisplayMeta::ExtendedLinearAddress(meta) => meta.modified,
+            _ => false,
+        }
+    }
+
+    fn set_modified(&mut self, modified: bool) {
+        match self {
+            IHexRecordDisplayMeta::Data(meta) => meta.modified = modified,
+            IHexRecordDisplayMeta::ExtendedSegmentAddress(meta)
+            | IHexRecordDisplayMeta::ExtendedLinearAddress(meta) => meta.modified = modified,
+            _ => {}
+        }
+    }
+}
+
+/// A row in the data table, which is either a record or a record that could not be read
+#[derive(Clone, Copy)]
+enum DataTableRow {
+    Record(usize),
+    Broken(usize),
+}
+
+impl DataTableRow {
+    /// Interleaves the broken records with the valid records, in file order
+    fn build_all(num_records: usize, diagnostics: &[IHexDiagnostic]) -> Vec<Self> {
+        let mut rows = Vec::with_capacity(num_records + diagnostics.len());
+        let mut diagnostics = diagnostics.iter().enumerate().peekable();
+
+        for i in 0..=num_records {
+            while let Some((diag_idx, _)) = diagnostics.next_if(|(_, d)| d.record_index <= i) {
+                rows.push(DataTableRow::Broken(diag_idx));
+            }
+
+            if i < num_records {
+                rows.push(DataTableRow::Record(i));
+            }
+        }
+
+        rows
+    }
 }
 
 struct DataTabMeta {
+    rows: Vec<DataTableRow>,
     record_meta: Vec<IHexRecordDisplayMeta>,
     set_all_to_mode: DataDisplayMode,
+    /// The record to scroll to in the next frame
+    scroll_to_record: Option<usize>,
 }
 
+#[derive(EnumIter, PartialEq, Eq, Clone, Copy)]
 enum MainPanelTab {
     Data,
+    Memory,
+    MemoryMap,
+    Compare,
+}
+
+impl MainPanelTab {
+    const fn as_str(&self) -> &'static str {
+        match self {
+            MainPanelTab::Data => "Data",
+            MainPanelTab::Memory => "Memory",
+            MainPanelTab::MemoryMap => "Memory Map",
+            MainPanelTab::Compare => "Compare",
+        }
+    }
 }
 
 struct MainPanelMeta {
     data: DataTabMeta,
+    memory: memory_tab::MemoryTabMeta,
+    layout: layout_tab::LayoutTabMeta,
+    /// The record that is highlighted in the data and memory tabs
+    selected_record: Option<usize>,
+    goto: goto::GoToState,
+    search: search::SearchState,
+    checksum: checksum_panel::ChecksumState,
+    /// The file the open file is compared with in the compare tab
+    compare: Option<compare_tab::CompareState>,
 }
 
+pub const WINDOW_TITLE: &str = "CPR IHEX - Intel HEX Parser and Manipulator";
+
 pub struct MainPanel {
     file: IHexFile,
+    /// The path the file was opened from or last saved to
+    path: Option<PathBuf>,
+    history: History,
+    show_history: bool,
+    show_search: bool,
+    show_checksum: bool,
+    diagnostics: Vec<IHexDiagnostic>,
+    /// The lines of a file in another format than Intel HEX that could not be read
+    skipped: Vec<String>,
+    /// The header written when saving as S-record
+    srec_header: Vec<u8>,
+    memory: MemoryMap,
     tab: MainPanelTab,
     meta: MainPanelMeta,
 }
 
 pub enum Gui {
-    OpenFile,
-    MainPanel(MainPanel),
+    OpenFile { error: Option<String> },
+    MainPanel(Box<MainPanel>),
 }
 
 impl Gui {
-    pub fn new(_cc: &eframe::CreationContext, file: Option<IHexFile>) -> Self {
-        let mut gui = Gui::OpenFile;
+    pub fn new(
+        _cc: &eframe::CreationContext,
+        file: Option<IHexFile>,
+        path: Option<PathBuf>,
+        diagnostics: Vec<IHexDiagnostic>,
+    ) -> Self {
+        let mut gui = Gui::OpenFile { error: None };
 
         if let Some(file) = file {
-            gui.file_opened(file)
+            let srec_header = path
+                .as_deref()
+                .map(open_file::read_srec_header)
+                .unwrap_or_default();
+
+            let opened = open_file::OpenedFile {
+                file,
+                diagnostics,
+                skipped: Vec::new(),
+                srec_header,
+            };
+
+            gui.file_opened(opened, path)
         }
 
         gui
     }
 
-    pub fn file_opened(&mut self, file: IHexFile) {
+    fn file_opened(&mut self, opened: open_file::OpenedFile, path: Option<PathBuf>) {
+        let open_file::OpenedFile {
+            file,
+            diagnostics,
+            skipped,
+            srec_header,
+        } = opened;
+
         let record_meta: Vec<_> = file
             .records
             .iter()
             .map(IHexRecordDisplayMeta::default_for)
             .collect();
-        *self = Gui::MainPanel(MainPanel {
-            file,
-            tab: MainPanelTab::Data,
+        let memory = file.data_bytes();
+
+        *self = Gui::MainPanel(Box::new(MainPanel {
             meta: MainPanelMeta {
                 data: DataTabMeta {
+                    rows: DataTableRow::build_all(file.records.len(), &diagnostics),
                     record_meta,
                     set_all_to_mode: DataDisplayMode::Bytes,
+                    scroll_to_record: None,
                 },
+                memory: memory_tab::MemoryTabMeta::new(&memory),
+                layout: layout_tab::LayoutTabMeta::new(&file, &memory),
+                selected_record: None,
+                goto: goto::GoToState::default(),
+                search: search::SearchState::default(),
+                checksum: checksum_panel::ChecksumState::default(),
+                compare: None,
             },
-        });
+            file,
+            memory,
+            path,
+            history: History::default(),
+            show_history: false,
+            show_search: false,
+            show_checksum: false,
+            diagnostics,
+            skipped,
+            srec_header,
+            tab: MainPanelTab::Data,
+        }));
     }
 }
 
 impl eframe::App for Gui {
     fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        let title = match self {
+            Gui::OpenFile { .. } => WINDOW_TITLE.to_string(),
+            Gui::MainPanel(main_panel) => main_panel.title(),
+        };
+
+        if ctx.input(|i| i.viewport().title.as_ref() != Some(&title)) {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title));
+        }
+
+        if let Gui::MainPanel(main_panel) = self {
+            if ctx.input(|i| i.viewport().close_requested()) && !main_panel.confirm_discard() {
+                ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            }
+        }
+
+        menu::menu_bar(self, ctx);
+
         CentralPanel::default().show(ctx, |ui| match self {
-            Gui::OpenFile => open_file::gui(self, ctx, frame, ui),
+            Gui::OpenFile { .. } => open_file::gui(self, ctx, frame, ui),
             Gui::MainPanel(main_panel) => main_panel::gui(main_panel, ctx, frame, ui),
         });
     }
@@ -1,49 +1,261 @@
 use std::{
+    fmt::Display,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Lines, Read},
+    path::{Path, PathBuf},
 };
 
 use eframe::{
-    egui::{Context, Ui},
+    egui::{Color32, Context, Ui},
     Frame,
 };
-use rfd::FileDialog;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageLevel};
 
-use crate::record::file::IHexFile;
+use cpr_ihex::{
+    formats::{elf::ElfFile, srec::SRecordFile, titxt::TiTxtFile, FileFormat},
+    memory::MemoryMap,
+    record::{
+        binary::{BinaryImportError, BinaryImportOptions},
+        file::{IHexDiagnostic, IHexFile},
+    },
+};
 
 use super::Gui;
 
+/// The amount of unreadable lines listed in the dialog after opening a file
+const MAX_LISTED_ERRORS: usize = 10;
+
+/// A file as opened in the GUI
+pub(super) struct OpenedFile {
+    pub(super) file: IHexFile,
+    /// The Intel HEX records that could not be read
+    pub(super) diagnostics: Vec<IHexDiagnostic>,
+    /// The lines of other formats that could not be read
+    pub(super) skipped: Vec<String>,
+    /// The S0 header of an S-record file, which is written back when saving as S-record
+    pub(super) srec_header: Vec<u8>,
+}
+
+impl OpenedFile {
+    fn new(file: IHexFile) -> Self {
+        OpenedFile {
+            file,
+            diagnostics: Vec::new(),
+            skipped: Vec::new(),
+            srec_header: Vec::new(),
+        }
+    }
+}
+
+/// A file dialog with a filter for every supported format, or only for the formats
+/// that can be written
+pub(super) fn file_dialog(writable_only: bool) -> FileDialog {
+    FileFormat::ALL
+        .iter()
+        .filter(|format| !writable_only || format.can_write())
+        .fold(FileDialog::new(), |dialog, format| {
+            dialog.add_filter(format.name(), format.extensions())
+        })
+        .add_filter("Others", &["*"])
+}
+
+pub(super) fn pick_file() -> Option<PathBuf> {
+    file_dialog(false).pick_file()
+}
+
+/// Checks the result of leniently reading a file in a format other than Intel HEX, and
+/// converts it to Intel HEX records. Returns the file and the lines that could not be read.
+fn convert_lenient<F, E: Display>(
+    (parsed, mut errors): (&F, Vec<E>),
+    memory: impl Fn(&F) -> &MemoryMap,
+    to_ihex: impl Fn(&F) -> Result<IHexFile, BinaryImportError>,
+) -> Result<OpenedFile, String> {
+    // If nothing could be read at all, the file is most likely in another format
+    if memory(parsed).is_empty() && !errors.is_empty() {
+        let e = errors.swap_remove(0);
+        log::error!("Could not parse file: {}", e);
+        return Err(format!("Could not parse file: {}", e));
+    }
+
+    for error in errors.iter() {
+        log::warn!("Skipped invalid record: {}", error);
+    }
+
+    let file = to_ihex(parsed).map_err(|e| {
+        log::error!("Could not convert file: {}", e);
+        format!("Could not convert file: {}", e)
+    })?;
+
+    Ok(OpenedFile {
+        skipped: errors.iter().map(ToString::to_string).collect(),
+        ..OpenedFile::new(file)
+    })
+}
+
+/// Tells the user which lines of the file at `path` could not be read and were skipped
+fn show_skipped(path: &Path, skipped: &[String]) {
+    let mut description = format!(
+        "{} line(s) of {} could not be read and were skipped. They are not written \
+         when the file is saved.\n",
+        skipped.len(),
+        path.display()
+    );
+
+    for error in skipped.iter().take(MAX_LISTED_ERRORS) {
+        description.push_str(&format!("\n{}", error));
+    }
+
+    if skipped.len() > MAX_LISTED_ERRORS {
+        description.push_str(&format!(
+            "\n... and {} more",
+            skipped.len() - MAX_LISTED_ERRORS
+        ));
+    }
+
+    MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("Unreadable lines")
+        .set_description(description)
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
+
+/// Reads the S0 header of the S-record file at `path`, or an empty header if it is not an
+/// S-record file or can not be read
+pub(super) fn read_srec_header(path: &Path) -> Vec<u8> {
+    if FileFormat::from_path(path) != Some(FileFormat::SRecord) {
+        return Vec::new();
+    }
+
+    match File::open(path) {
+        Ok(file) => {
+            SRecordFile::read_lenient(BufReader::new(file).lines())
+                .0
+                .header
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Opens and leniently parses the file at `path`, in the format detected from its extension
+/// and contents.
+/// Lines of other formats than Intel HEX that could not be read are shown in a dialog.
+/// Returns a description of the error if the file could not be opened.
+pub(super) fn open_path(path: &Path) -> Result<OpenedFile, String> {
+    log::debug!("Opening file: {}", path.display());
+
+    let file = File::open(path).map_err(|e| {
+        log::error!("Could not open file: {}", e);
+        format!("Could not open file: {}", e)
+    })?;
+
+    log::info!("File opened successfully");
+    log::debug!("Parsing file");
+
+    let mut reader = BufReader::new(file);
+    let options = BinaryImportOptions::default();
+
+    let contents = reader.fill_buf().map_err(|e| {
+        log::error!("Could not read file: {}", e);
+        format!("Could not read file: {}", e)
+    })?;
+
+    let opened = match FileFormat::detect(path, contents).unwrap_or_default() {
+        FileFormat::IHex => return read_ihex(reader.lines()),
+        FileFormat::SRecord => {
+            let (srec, errors) = SRecordFile::read_lenient(reader.lines());
+
+            OpenedFile {
+                srec_header: srec.header.clone(),
+                ..convert_lenient(
+                    (&srec, errors),
+                    |srec| &srec.memory,
+                    |srec| srec.to_ihex(&options),
+                )?
+            }
+        }
+        FileFormat::TiTxt => {
+            let (titxt, errors) = TiTxtFile::read_lenient(reader.lines());
+
+            convert_lenient(
+                (&titxt, errors),
+                |titxt| &titxt.memory,
+                |titxt| titxt.to_ihex(&options),
+            )?
+        }
+        FileFormat::Elf => OpenedFile::new(read_elf(reader, &options)?),
+    };
+
+    log::info!("File parsed successfully");
+
+    // Skipped lines of other formats have no place among the generated Intel HEX records,
+    // so they are only shown in a dialog
+    if !opened.skipped.is_empty() {
+        show_skipped(path, &opened.skipped);
+    }
+
+    Ok(opened)
+}
+
+fn read_elf(
+    mut reader: BufReader<File>,
+    options: &BinaryImportOptions,
+) -> Result<IHexFile, String> {
+    let mut data = Vec::new();
+
+    reader.read_to_end(&mut data).map_err(|e| {
+        log::error!("Could not read file: {}", e);
+        format!("Could not read file: {}", e)
+    })?;
+
+    let elf = ElfFile::read(&data).map_err(|e| {
+        log::error!("Could not parse file: {}", e);
+        format!("Could not parse file: {}", e)
+    })?;
+
+    elf.to_ihex(options).map_err(|e| {
+        log::error!("Could not convert file: {}", e);
+        format!("Could not convert file: {}", e)
+    })
+}
+
+fn read_ihex(lines: Lines<BufReader<File>>) -> Result<OpenedFile, String> {
+    let (parsed, mut diagnostics) = IHexFile::read_lenient(lines);
+
+    // If nothing could be read at all, this is most likely not an Intel HEX file
+    if parsed.records.is_empty() && !diagnostics.is_empty() {
+        let e = diagnostics.swap_remove(0).error;
+        log::error!("Could not parse file: {}", e);
+        return Err(format!("Could not parse file: {}", e));
+    }
+
+    for diagnostic in diagnostics.iter() {
+        log::warn!("Skipped invalid record: {}", diagnostic.error);
+    }
+
+    log::info!("File parsed successfully");
+
+    Ok(OpenedFile {
+        diagnostics,
+        ..OpenedFile::new(parsed)
+    })
+}
+
 pub(super) fn gui(gui: &mut Gui, _ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
     ui.vertical_centered(|ui| {
         ui.heading("Select file");
         if ui.button("Open").clicked() {
-            let picked_path = FileDialog::new()
-                .add_filter("Intel HEX", &["hex"])
-                .add_filter("Others", &["*"])
-                .pick_file();
-
-            if let Some(path) = picked_path {
-                log::debug!("Opening file: {}", path.display());
-
-                let file = File::open(path);
-
-                match file {
-                    Ok(file) => {
-                        log::info!("File opened successfully");
-                        log::debug!("Parsing file");
-                        let parsed = IHexFile::read(BufReader::new(file).lines());
-
-                        match parsed {
-                            Ok(parsed) => {
-                                log::info!("File parsed successfully");
-                                gui.file_opened(parsed);
-                            }
-                            Err(e) => log::error!("Could not parse file: {}", e),
-                        }
-                    }
-                    Err(e) => log::error!("Could not open file: {}", e),
+            if let Some(path) = pick_file() {
+                match open_path(&path) {
+                    Ok(opened) => gui.file_opened(opened, Some(path)),
+                    Err(e) => *gui = Gui::OpenFile { error: Some(e) },
                 }
             }
         }
+
+        if let Gui::OpenFile { error: Some(error) } = gui {
+            ui.add_space(10.0);
+            ui.colored_label(Color32::RED, error.as_str());
+        }
     });
 }
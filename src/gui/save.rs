@@ -0,0 +1,181 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use cpr_ihex::{
+    formats::{
+        srec::{SRecordFile, SRecordWriteOptions},
+        titxt::{TiTxtFile, TiTxtWriteOptions},
+        FileFormat,
+    },
+    record::file::{IHexFile, IHexWriteOptions},
+};
+
+use crate::cli::commands::replace_file;
+
+use super::{open_file::file_dialog, MainPanel};
+
+/// Writes the file in the format matching the extension of `path`, falling back to Intel HEX.
+/// S-record files are written with `srec_header` as their header. An existing file is only
+/// replaced once the whole file was serialized.
+fn write_file(file: &IHexFile, path: &Path, srec_header: &[u8]) -> std::io::Result<()> {
+    let format = FileFormat::from_path(path).unwrap_or_default();
+
+    if !format.can_write() {
+        return Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            format!("{} files can not be written", format.name()),
+        ));
+    }
+
+    let mut writer = Vec::new();
+
+    match format {
+        FileFormat::IHex => file.write(&mut writer, &IHexWriteOptions::default())?,
+        FileFormat::SRecord => SRecordFile {
+            header: srec_header.to_vec(),
+            ..SRecordFile::from_ihex(file)
+        }
+        .write(&mut writer, &SRecordWriteOptions::default())?,
+        FileFormat::TiTxt => {
+            TiTxtFile::from_ihex(file).write(&mut writer, &TiTxtWriteOptions::default())?
+        }
+        FileFormat::Elf => unreachable!("Checked by FileFormat::can_write"),
+    }
+
+    replace_file(path, &writer)
+}
+
+impl MainPanel {
+    /// Saves the file to the path it was opened from, or asks for a path if there is none
+    /// or the file was opened from a format that can not be written. Returns true if the
+    /// file was saved.
+    pub(super) fn save(&mut self) -> bool {
+        match self.path.clone() {
+            Some(path) if FileFormat::from_path(&path).unwrap_or_default().can_write() => {
+                self.save_to(path)
+            }
+            _ => self.save_as(),
+        }
+    }
+
+    /// Asks for a path and saves the file there. Returns true if the file was saved.
+    pub(super) fn save_as(&mut self) -> bool {
+        let mut dialog = file_dialog(true);
+
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                dialog = dialog.set_directory(dir);
+            }
+
+            // Suggest Intel HEX for files opened from a format that can not be written
+            let path = match FileFormat::from_path(path).unwrap_or_default().can_write() {
+                true => path.clone(),
+                false => path.with_extension("hex"),
+            };
+
+            if let Some(name) = path.file_name() {
+                dialog = dialog.set_file_name(name.to_string_lossy());
+            }
+        }
+
+        match dialog.save_file() {
+            Some(path) => self.save_to(path),
+            None => false,
+        }
+    }
+
+    /// Asks whether the records or lines that could not be read may be dropped from the file at `path`.
+    /// Only asked when overwriting the file they were read from. Returns true if the file may
+    /// be written.
+    fn confirm_drop_broken(&self, path: &Path) -> bool {
+        let broken = self.diagnostics.len() + self.skipped.len();
+
+        if broken == 0 || self.path.as_deref() != Some(path) {
+            return true;
+        }
+
+        let answer = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Unreadable records")
+            .set_description(format!(
+                "{} contains {} record(s) that could not be read. They will be removed from the \
+                 file if it is overwritten. Use Save As to keep the original file.\n\n\
+                 Overwrite the file anyway?",
+                path.display(),
+                broken
+            ))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+
+        answer == MessageDialogResult::Yes
+    }
+
+    fn save_to(&mut self, path: PathBuf) -> bool {
+        if !self.confirm_drop_broken(&path) {
+            return false;
+        }
+
+        log::debug!("Saving file: {}", path.display());
+
+        match write_file(&self.file, &path, &self.srec_header) {
+            Ok(()) => {
+                log::info!("File saved successfully");
+                self.path = Some(path);
+                self.history.mark_saved();
+                true
+            }
+            Err(e) => {
+                log::error!("Could not save file: {}", e);
+
+                MessageDialog::new()
+                    .set_level(MessageLevel::Error)
+                    .set_title("Could not save file")
+                    .set_description(format!("Could not save {}: {}", path.display(), e))
+                    .set_buttons(MessageButtons::Ok)
+                    .show();
+
+                false
+            }
+        }
+    }
+
+    /// If there are unsaved changes, asks whether they should be saved first.
+    /// Returns true if the current file may be closed.
+    pub(super) fn confirm_discard(&mut self) -> bool {
+        if !self.history.is_dirty() {
+            return true;
+        }
+
+        let answer = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Unsaved changes")
+            .set_description("The file has unsaved changes. Do you want to save them first?")
+            .set_buttons(MessageButtons::YesNoCancel)
+            .show();
+
+        match answer {
+            MessageDialogResult::Yes => self.save(),
+            MessageDialogResult::No => true,
+            _ => false,
+        }
+    }
+
+    /// The window title, including the file name and whether there are unsaved changes
+    pub(super) fn title(&self) -> String {
+        let name = match &self.path {
+            Some(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            None => "Untitled".to_string(),
+        };
+
+        let dirty_marker = if self.history.is_dirty() { "*" } else { "" };
+
+        format!("{}{} - {}", dirty_marker, name, super::WINDOW_TITLE)
+    }
+}
@@ -0,0 +1,195 @@
+use eframe::egui::{
+    Color32, Context, Id, Key, KeyboardShortcut, Modifiers, ScrollArea, SidePanel, TextEdit,
+};
+
+use cpr_ihex::{memory::MemoryMap, search::SearchPattern};
+
+use super::{main_panel::display_mode_combobox, DataDisplayMode, MainPanel};
+
+pub(super) const SEARCH_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+
+/// Searches matching everything (e.g. `??`) would otherwise list the entire image
+const MAX_RESULTS: usize = 10_000;
+
+pub(super) struct SearchState {
+    mode: DataDisplayMode,
+    query: String,
+    /// The pattern of the last search, which is repeated when the file changes
+    pattern: Option<SearchPattern>,
+    results: Vec<u32>,
+    truncated: bool,
+    /// The index of the result that was navigated to last
+    current: Option<usize>,
+    error: Option<String>,
+    focus: bool,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        SearchState {
+            mode: DataDisplayMode::Bytes,
+            query: String::new(),
+            pattern: None,
+            results: Vec::new(),
+            truncated: false,
+            current: None,
+            error: None,
+            focus: false,
+        }
+    }
+}
+
+impl SearchState {
+    /// Requests keyboard focus for the search field in the next frame
+    pub(super) fn focus(&mut self) {
+        self.focus = true;
+    }
+
+    fn search(&mut self, memory: &MemoryMap) {
+        let pattern = match self.mode.text_encoding() {
+            Some(encoding) => SearchPattern::from_text(&self.query, encoding),
+            None => SearchPattern::from_hex(&self.query),
+        };
+
+        match pattern {
+            Ok(pattern) => {
+                self.error = None;
+                self.pattern = Some(pattern);
+                self.refresh(memory);
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                self.pattern = None;
+                self.results.clear();
+                self.current = None;
+            }
+        }
+    }
+
+    /// Repeats the last search, e.g. after the file was edited
+    pub(super) fn refresh(&mut self, memory: &MemoryMap) {
+        let Some(pattern) = &self.pattern else {
+            return;
+        };
+
+        self.results = pattern.find_iter(memory).take(MAX_RESULTS + 1).collect();
+        self.truncated = self.results.len() > MAX_RESULTS;
+        self.results.truncate(MAX_RESULTS);
+        self.current = None;
+    }
+
+    /// The index of the next (or previous) result, wrapping around at the ends
+    fn step(&self, forward: bool) -> Option<usize> {
+        let len = self.results.len();
+
+        if len == 0 {
+            return None;
+        }
+
+        Some(match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(current), true) => (current + 1) % len,
+            (Some(current), false) => (current + len - 1) % len,
+        })
+    }
+}
+
+impl MainPanel {
+    fn go_to_result(&mut self, index: usize) {
+        let state = &mut self.meta.search;
+        let address = state.results[index];
+        let length = state.pattern.as_ref().map_or(1, SearchPattern::len);
+
+        state.current = Some(index);
+
+        // Results are always mapped, so this can not fail
+        let _ = self.go_to_address(address);
+        self.meta.memory.selected_length = length as u32;
+    }
+}
+
+/// Shows the search panel, with the search field and the list of results
+pub(super) fn search_panel(panel: &mut MainPanel, ctx: &Context) {
+    let mut go_to = None;
+
+    SidePanel::left("search_panel")
+        .resizable(true)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            let state = &mut panel.meta.search;
+            let id = Id::new("search_field");
+
+            ui.heading("Search");
+            ui.separator();
+
+            display_mode_combobox("search_mode_box", &mut state.mode, ui);
+
+            let hint = match state.mode {
+                DataDisplayMode::Bytes => "DE AD ?? EF",
+                _ => "Text",
+            };
+
+            let response = ui.add(
+                TextEdit::singleline(&mut state.query)
+                    .id(id)
+                    .hint_text(hint)
+                    .desired_width(f32::INFINITY),
+            );
+
+            if state.focus {
+                response.request_focus();
+                state.focus = false;
+            }
+
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+
+            ui.horizontal(|ui| {
+                if ui.button("Find").clicked() || entered {
+                    state.search(&panel.memory);
+                    go_to = state.step(true);
+                }
+
+                if ui.button("Previous").clicked() {
+                    go_to = state.step(false);
+                }
+
+                if ui.button("Next").clicked() {
+                    go_to = state.step(true);
+                }
+            });
+
+            if let Some(error) = &state.error {
+                ui.colored_label(Color32::RED, error);
+            } else if state.pattern.is_some() {
+                let count = match state.truncated {
+                    true => format!("More than {} matches", MAX_RESULTS),
+                    false => format!("{} match(es)", state.results.len()),
+                };
+
+                ui.label(count);
+            }
+
+            ui.separator();
+
+            ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show_rows(ui, 18.0, state.results.len(), |ui, rows| {
+                    for i in rows {
+                        let text = format!("0x{:08X}", state.results[i]);
+
+                        if ui
+                            .selectable_label(state.current == Some(i), text)
+                            .clicked()
+                        {
+                            go_to = Some(i);
+                        }
+                    }
+                });
+        });
+
+    if let Some(index) = go_to {
+        panel.go_to_result(index);
+    }
+}
@@ -0,0 +1,62 @@
+//! A parser and writer for the Intel HEX format.
+//!
+//! The main entry point is [`IHexFile`], which can be read from text, generated from a
+//! binary image, validated and written back to text. The data contents of a file are
+//! available as a sparse [`MemoryMap`]. Other object file formats, such as Motorola
+//! S-records, TI-TXT and ELF executables, can be converted to an [`IHexFile`] with the
+//! [`formats`] module, which can also export the data as C or Rust source code and as
+//! memory initialization files for FPGAs. Several files can be combined into one with
+//! [`merge()`], and compared by address with [`ImageDiff`].
+//!
+//! ```
+//! use std::io::BufRead;
+//! use cpr_ihex::{IHexFile, IHexWriteOptions};
+//!
+//! let text = ":0400100011111111A8\n:00000001FF\n";
+//! let file = IHexFile::read(text.as_bytes().lines()).unwrap();
+//!
+//! assert_eq!(file.data_bytes().min_address(), Some(0x10));
+//! assert_eq!(file.to_ihex_string(&IHexWriteOptions::default()).unwrap(), text);
+//! ```
+//!
+//! The command line interface and GUI of the `cpr_ihex` binary are behind the `cli` and
+//! `gui` features. Disable the default features to only depend on the library.
+
+pub mod checksum;
+pub mod diff;
+pub mod formats;
+pub mod memory;
+pub mod merge;
+pub mod record;
+pub mod search;
+mod utils;
+
+pub use checksum::{memory_checksum, Checksum, ChecksumAlgorithm, ChecksumValue};
+pub use diff::{DiffKind, DiffRange, ImageDiff};
+pub use formats::{
+    elf::{ElfFile, ElfReadError},
+    meminit::{write_meminit, MemInitError, MemInitFormat, MemInitOptions},
+    source::{write_source, SourceExportError, SourceLanguage, SourceWriteOptions},
+    srec::{
+        SRecordAddressWidth, SRecordFile, SRecordReadError, SRecordReadErrorKind,
+        SRecordWriteOptions,
+    },
+    titxt::{TiTxtFile, TiTxtReadError, TiTxtReadErrorKind, TiTxtWriteOptions},
+    FileFormat,
+};
+pub use memory::{
+    parse_address, parse_number, AddressParseError, Endianness, MemoryMap, MemorySegment,
+};
+pub use merge::{merge, ConflictPolicy, MergeError, MergeOptions};
+pub use record::{
+    binary::{AddressingMode, BinaryImportError, BinaryImportOptions},
+    file::{
+        IHexDiagnostic, IHexFile, IHexFileType, IHexReadError, IHexReadErrorKind, IHexWriteOptions,
+        InvalidIHexRecordError, LineEnding, SegmentStartAddr, StartAddr,
+    },
+    raw::{parse_ihex, HexCase, IHexParseError, RawIHexRecord, RecordTooLongError},
+    validate::{Severity, ValidationIssue, ValidationIssueKind, ValidationOptions},
+    DataRecord, EndOfFileRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord,
+    IHexRecord, StartLinearAddressRecord, StartSegmentAddressRecord,
+};
+pub use search::{PatternError, SearchPattern, TextEncoding};
@@ -1,22 +1,19 @@
 mod cli;
+#[cfg(feature = "gui")]
 mod gui;
-mod record;
-mod utils;
 use anyhow::{anyhow, Result};
-use cli::{args::CLIArgs, commands::run_commands};
-use gui::Gui;
-use record::file::IHexFile;
-
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+use cli::{
+    args::CLIArgs,
+    commands::{read_file, run_subcommand},
 };
+use cpr_ihex::{IHexDiagnostic, IHexFile};
+
+use std::path::PathBuf;
 
 use clap::Parser;
-use eframe::{egui::ViewportBuilder, NativeOptions};
 use simplelog::{ColorChoice, ConfigBuilder, TermLogger, TerminalMode};
 
-fn setup() -> Result<(CLIArgs, Option<File>)> {
+fn setup() -> Result<CLIArgs> {
     color_backtrace::install();
 
     let args = cli::args::CLIArgs::parse();
@@ -37,40 +34,65 @@ fn setup() -> Result<(CLIArgs, Option<File>)> {
 
     log::info!("Logger initialized");
 
-    let file = match &args.file {
-        Some(file) => Some(
-            File::open(file)
-                .map_err(|e| anyhow!("I/O Error while opening provided file: {}", e))?,
-        ),
-        None => None,
-    };
-
-    Ok((args, file))
+    Ok(args)
 }
 
 fn main() -> Result<()> {
-    let (args, provided_file) = setup().map_err(|e| anyhow!("Setup failed: {}", e))?;
-    let parsed_file = match provided_file {
-        Some(file) => Some(IHexFile::read(BufReader::new(file).lines())?),
-        None => None,
-    };
+    let args = setup().map_err(|e| anyhow!("Setup failed: {}", e))?;
 
-    if run_commands(&args, parsed_file.as_ref())? {
+    if let Some(subcommand) = &args.subcommand {
+        run_subcommand(subcommand)?;
         return Ok(());
     }
 
+    let (parsed_file, diagnostics) = match &args.file {
+        Some(path) => {
+            let read = read_file(path, None, args.lenient, Some(&args.binary_options))?;
+            (Some(read.file), read.diagnostics)
+        }
+        None => (None, Vec::new()),
+    };
+
+    // A binary image can not be saved back to its source path as Intel HEX
+    let source_path = match args.binary_options.binary_base {
+        Some(_) => None,
+        None => args.file.clone(),
+    };
+
+    run_gui(parsed_file, source_path, diagnostics)
+}
+
+#[cfg(feature = "gui")]
+fn run_gui(
+    parsed_file: Option<IHexFile>,
+    source_path: Option<PathBuf>,
+    diagnostics: Vec<IHexDiagnostic>,
+) -> Result<()> {
+    use eframe::{egui::ViewportBuilder, NativeOptions};
+    use gui::{Gui, WINDOW_TITLE};
+
     let native_options = NativeOptions {
-        viewport: ViewportBuilder::default()
-            .with_title("CPR IHEX - Intel HEX Parser and Manipulator"),
+        viewport: ViewportBuilder::default().with_title(WINDOW_TITLE),
         ..Default::default()
     };
 
     eframe::run_native(
         "io.wutru.cpr_ihex",
         native_options,
-        Box::new(move |cc| Box::new(Gui::new(cc, parsed_file))),
+        Box::new(move |cc| Box::new(Gui::new(cc, parsed_file, source_path, diagnostics))),
     )
     .expect("Could not run GUI");
 
     Ok(())
 }
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(
+    _parsed_file: Option<IHexFile>,
+    _source_path: Option<PathBuf>,
+    _diagnostics: Vec<IHexDiagnostic>,
+) -> Result<()> {
+    Err(anyhow!(
+        "No command was given, and this build does not include the GUI"
+    ))
+}
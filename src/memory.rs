@@ -0,0 +1,509 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+/// The order in which the bytes of a multi-byte value are stored in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    /// The least significant byte is stored at the lowest address.
+    #[default]
+    Little,
+    /// The most significant byte is stored at the lowest address.
+    Big,
+}
+
+/// A contiguous block of bytes in the address space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySegment {
+    pub start: u32,
+    pub data: Vec<u8>,
+}
+
+impl MemorySegment {
+    /// The address one past the last byte of the segment.
+    pub fn end(&self) -> u64 {
+        self.start as u64 + self.data.len() as u64
+    }
+}
+
+/// A sparse memory image, stored as an ordered list of contiguous segments.
+///
+/// Segments never overlap or touch: writing to an address range that overlaps
+/// or is adjacent to existing segments merges them into a single segment.
+/// Addresses that are not covered by any segment are unmapped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+    segments: Vec<MemorySegment>,
+}
+
+const ADDRESS_SPACE_END: u64 = 1 << 32;
+
+impl MemoryMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[MemorySegment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The total amount of mapped bytes.
+    pub fn len(&self) -> u64 {
+        self.segments.iter().map(|seg| seg.data.len() as u64).sum()
+    }
+
+    /// The lowest mapped address, if any.
+    pub fn min_address(&self) -> Option<u32> {
+        self.segments.first().map(|seg| seg.start)
+    }
+
+    /// The highest mapped address, if any.
+    pub fn max_address(&self) -> Option<u32> {
+        self.segments.last().map(|seg| (seg.end() - 1) as u32)
+    }
+
+    /// The segment containing `address`, if it is mapped.
+    pub fn segment_at(&self, address: u32) -> Option<&MemorySegment> {
+        let idx = self
+            .segments
+            .partition_point(|seg| seg.end() <= address as u64);
+
+        self.segments.get(idx).filter(|seg| seg.start <= address)
+    }
+
+    /// The byte at `address`, if it is mapped.
+    pub fn get(&self, address: u32) -> Option<u8> {
+        self.segment_at(address)
+            .map(|seg| seg.data[(address - seg.start) as usize])
+    }
+
+    /// Writes `data` starting at `address`, overwriting any bytes already present.
+    /// Data that would extend past the end of the 32-bit address space is discarded.
+    pub fn insert(&mut self, address: u32, data: &[u8]) {
+        let start = address as u64;
+        let end = (start + data.len() as u64).min(ADDRESS_SPACE_END);
+        let data = &data[..(end - start) as usize];
+
+        if data.is_empty() {
+            return;
+        }
+
+        // All segments that overlap or touch the new range get merged with it
+        let first = self.segments.partition_point(|seg| seg.end() < start);
+        let last = self
+            .segments
+            .partition_point(|seg| (seg.start as u64) <= end);
+
+        if first == last {
+            self.segments.insert(
+                first,
+                MemorySegment {
+                    start: address,
+                    data: data.to_vec(),
+                },
+            );
+            return;
+        }
+
+        let merged_start = start.min(self.segments[first].start as u64);
+        let merged_end = end.max(self.segments[last - 1].end());
+
+        let mut merged = if self.segments[first].start as u64 == merged_start {
+            std::mem::take(&mut self.segments[first].data)
+        } else {
+            Vec::new()
+        };
+
+        merged.resize((merged_end - merged_start) as usize, 0);
+
+        for seg in &self.segments[first..last] {
+            if seg.data.is_empty() {
+                continue;
+            }
+
+            let offset = (seg.start as u64 - merged_start) as usize;
+            merged[offset..offset + seg.data.len()].copy_from_slice(&seg.data);
+        }
+
+        let offset = (start - merged_start) as usize;
+        merged[offset..offset + data.len()].copy_from_slice(data);
+
+        self.segments.splice(
+            first..last,
+            [MemorySegment {
+                start: merged_start as u32,
+                data: merged,
+            }],
+        );
+    }
+
+    /// Reads `len` bytes starting at `address`, using `fill` for unmapped bytes.
+    pub fn read(&self, address: u32, len: usize, fill: u8) -> Vec<u8> {
+        let start = address as u64;
+        let end = start + len as u64;
+        let mut result = vec![fill; len];
+
+        let first = self.segments.partition_point(|seg| seg.end() <= start);
+
+        for seg in self.segments[first..]
+            .iter()
+            .take_while(|seg| (seg.start as u64) < end)
+        {
+            let copy_start = start.max(seg.start as u64);
+            let copy_end = end.min(seg.end());
+
+            let src = &seg.data
+                [(copy_start - seg.start as u64) as usize..(copy_end - seg.start as u64) as usize];
+            result[(copy_start - start) as usize..(copy_end - start) as usize].copy_from_slice(src);
+        }
+
+        result
+    }
+
+    /// Flattens the image into a single buffer, starting at the lowest mapped
+    /// address and using `fill` for any gaps between segments.
+    pub fn to_flat(&self, fill: u8) -> Vec<u8> {
+        match (self.min_address(), self.max_address()) {
+            (Some(min), Some(max)) => self.read(min, (max - min) as usize + 1, fill),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The pages of `page_size` bytes that contain at least one mapped byte, as
+    /// ranges of page numbers. Adjacent occupied pages are merged into one range.
+    pub fn occupied_pages(&self, page_size: u32) -> Vec<Range<u64>> {
+        let page_size = page_size.max(1) as u64;
+        let mut pages: Vec<Range<u64>> = Vec::new();
+
+        for seg in &self.segments {
+            let first = seg.start as u64 / page_size;
+            let last = (seg.end() - 1) / page_size;
+
+            match pages.last_mut() {
+                Some(prev) if first <= prev.end => prev.end = prev.end.max(last + 1),
+                _ => pages.push(first..last + 1),
+            }
+        }
+
+        pages
+    }
+
+    /// The unmapped address ranges between segments, as `start..end` pairs.
+    pub fn gaps(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.segments
+            .windows(2)
+            .map(|pair| pair[0].end()..pair[1].start as u64)
+    }
+
+    /// The address ranges that are mapped in both `self` and `other`, in address order.
+    pub fn overlapping_ranges(&self, other: &MemoryMap) -> Vec<Range<u64>> {
+        let mut ranges = Vec::new();
+        let (mut a, mut b) = (
+            self.segments.iter().peekable(),
+            other.segments.iter().peekable(),
+        );
+
+        while let (Some(left), Some(right)) = (a.peek(), b.peek()) {
+            let start = (left.start as u64).max(right.start as u64);
+            let end = left.end().min(right.end());
+
+            if start < end {
+                ranges.push(start..end);
+            }
+
+            if left.end() <= right.end() {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        ranges
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressParseError {
+    Empty,
+    InvalidNumber(String),
+    /// The number does not fit in the type it is parsed as.
+    OutOfRange(String),
+}
+
+impl Display for AddressParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressParseError::Empty => write!(f, "No value given"),
+            AddressParseError::InvalidNumber(text) => write!(f, "Invalid number: \"{}\"", text),
+            AddressParseError::OutOfRange(text) => write!(f, "\"{}\" is out of range", text),
+        }
+    }
+}
+
+impl Error for AddressParseError {}
+
+/// Parses an effective address, given as a hexadecimal number prefixed with `0x`,
+/// a decimal number, or in `CS:IP` segment notation with both parts in hexadecimal.
+pub fn parse_address(text: &str) -> Result<u32, AddressParseError> {
+    let trimmed = text.trim();
+
+    match trimmed.split_once(':') {
+        Some((cs, ip)) => {
+            let invalid = || AddressParseError::InvalidNumber(trimmed.to_string());
+            let code_segment = u16::from_str_radix(cs.trim(), 16).map_err(|_| invalid())?;
+            let instruction_pointer = u16::from_str_radix(ip.trim(), 16).map_err(|_| invalid())?;
+
+            Ok(((code_segment as u32) << 4) + instruction_pointer as u32)
+        }
+        None => parse_u32(trimmed),
+    }
+}
+
+/// Parses a hexadecimal number prefixed with `0x` or a decimal number
+fn parse_u32(text: &str) -> Result<u32, AddressParseError> {
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Err(AddressParseError::Empty);
+    }
+
+    let parsed = match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+
+    parsed.map_err(|_| AddressParseError::InvalidNumber(text.to_string()))
+}
+
+/// Parses a number that is not an address, as hexadecimal prefixed with `0x` or as decimal,
+/// and checks that it fits in `T`. Unlike [`parse_address`], `CS:IP` notation is not accepted.
+pub fn parse_number<T: TryFrom<u32>>(text: &str) -> Result<T, AddressParseError> {
+    let value = parse_u32(text)?;
+
+    T::try_from(value).map_err(|_| AddressParseError::OutOfRange(text.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(memory: &MemoryMap) -> Vec<(u32, Vec<u8>)> {
+        memory
+            .segments()
+            .iter()
+            .map(|seg| (seg.start, seg.data.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn insert_separate_segments() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x10, &[1, 2]);
+        memory.insert(0x20, &[3]);
+        memory.insert(0x00, &[4]);
+
+        assert_eq!(
+            segments(&memory),
+            [(0x00, vec![4]), (0x10, vec![1, 2]), (0x20, vec![3])]
+        );
+        assert_eq!(memory.len(), 4);
+        assert_eq!(memory.min_address(), Some(0x00));
+        assert_eq!(memory.max_address(), Some(0x20));
+    }
+
+    #[test]
+    fn insert_touching_segments() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x10, &[1, 2]);
+        memory.insert(0x12, &[3]);
+        memory.insert(0x0E, &[4, 5]);
+
+        assert_eq!(segments(&memory), [(0x0E, vec![4, 5, 1, 2, 3])]);
+    }
+
+    #[test]
+    fn insert_spanning_segments() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0, &[1, 1, 1]);
+        memory.insert(4, &[2, 2]);
+        memory.insert(8, &[3]);
+        memory.insert(12, &[4]);
+
+        // Starts inside the first segment and ends inside the third
+        memory.insert(2, &[9; 7]);
+
+        assert_eq!(
+            segments(&memory),
+            [(0, vec![1, 1, 9, 9, 9, 9, 9, 9, 9]), (12, vec![4])]
+        );
+
+        // Covers every segment, starting before the first
+        memory.insert(0x1_0000, &[5]);
+        memory.insert(0, &[7; 13]);
+
+        assert_eq!(segments(&memory), [(0, vec![7; 13]), (0x1_0000, vec![5])]);
+    }
+
+    #[test]
+    fn insert_overwrites() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0, &[1, 2, 3, 4]);
+        memory.insert(1, &[9, 9]);
+
+        assert_eq!(segments(&memory), [(0, vec![1, 9, 9, 4])]);
+
+        memory.insert(3, &[8, 8, 8]);
+
+        assert_eq!(segments(&memory), [(0, vec![1, 9, 9, 8, 8, 8])]);
+        assert_eq!(memory.len(), 6);
+    }
+
+    #[test]
+    fn insert_at_end_of_address_space() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0xFFFF_FFFE, &[1, 2, 3]);
+
+        assert_eq!(segments(&memory), [(0xFFFF_FFFE, vec![1, 2])]);
+        assert_eq!(memory.max_address(), Some(0xFFFF_FFFF));
+
+        memory.insert(0xFFFF_FFFF, &[]);
+        memory.insert(0xFFFF_FFFD, &[7]);
+
+        assert_eq!(segments(&memory), [(0xFFFF_FFFD, vec![7, 1, 2])]);
+        assert_eq!(memory.read(0xFFFF_FFFC, 4, 0), [0, 7, 1, 2]);
+    }
+
+    #[test]
+    fn read_gaps_and_flatten() {
+        let mut memory = MemoryMap::new();
+
+        assert!(memory.to_flat(0).is_empty());
+        assert_eq!(memory.gaps().count(), 0);
+
+        memory.insert(0x10, &[1, 2]);
+        memory.insert(0x14, &[3]);
+        memory.insert(0x20, &[4]);
+
+        assert_eq!(
+            memory.read(0x0E, 8, 0xFF),
+            [0xFF, 0xFF, 1, 2, 0xFF, 0xFF, 3, 0xFF]
+        );
+        assert_eq!(memory.read(0x11, 0, 0xFF), []);
+        assert_eq!(memory.gaps().collect::<Vec<_>>(), [0x12..0x14, 0x15..0x20]);
+        assert_eq!(memory.to_flat(0)[..6], [1, 2, 0, 0, 3, 0]);
+        assert_eq!(memory.to_flat(0).len(), 0x11);
+    }
+
+    #[test]
+    fn segment_at_and_get() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x10, &[1, 2]);
+        memory.insert(0x14, &[3]);
+        memory.insert(0xFFFF_FFFF, &[4]);
+
+        assert_eq!(memory.get(0x0F), None);
+        assert_eq!(memory.get(0x11), Some(2));
+        assert_eq!(memory.get(0x12), None);
+        assert_eq!(memory.get(0xFFFF_FFFF), Some(4));
+        assert_eq!(memory.segment_at(0x14).map(|seg| seg.start), Some(0x14));
+        assert_eq!(memory.segment_at(0x15), None);
+    }
+
+    #[test]
+    fn occupied_pages() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x0010, &[1]);
+        memory.insert(0x0FFF, &[2, 3]);
+        memory.insert(0x3000, &[4]);
+        memory.insert(0x3FFF, &[5]);
+        memory.insert(0xFFFF_FFFF, &[6]);
+
+        assert_eq!(
+            memory.occupied_pages(0x1000),
+            [0..2, 3..4, 0xF_FFFF..0x10_0000]
+        );
+        assert_eq!(memory.occupied_pages(0x10000), [0..1, 0xFFFF..0x1_0000]);
+
+        // A page size of 0 is treated as 1
+        assert_eq!(memory.occupied_pages(0)[0], 0x10..0x11);
+        assert!(MemoryMap::new().occupied_pages(0x1000).is_empty());
+    }
+
+    #[test]
+    fn parse_addresses() {
+        assert_eq!(parse_address("0x1F"), Ok(0x1F));
+        assert_eq!(parse_address("0XfF"), Ok(0xFF));
+        assert_eq!(parse_address(" 31 "), Ok(31));
+        assert_eq!(parse_address("1000:0010"), Ok(0x1_0010));
+        assert_eq!(parse_address("FFFF:FFFF"), Ok(0x10_FFEF));
+        assert_eq!(parse_address("0xFFFFFFFF"), Ok(0xFFFF_FFFF));
+
+        assert_eq!(parse_address(""), Err(AddressParseError::Empty));
+        assert_eq!(parse_address("  "), Err(AddressParseError::Empty));
+
+        for text in [
+            "1F",
+            "0x",
+            "0x1_0000_0000",
+            "4294967296",
+            "1:2:3",
+            "1:",
+            "-1",
+        ] {
+            assert_eq!(
+                parse_address(text),
+                Err(AddressParseError::InvalidNumber(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_number::<u8>("0xFF"), Ok(0xFF));
+        assert_eq!(parse_number::<u16>(" 0x10 "), Ok(0x10));
+        assert_eq!(parse_number::<u32>("4294967295"), Ok(u32::MAX));
+        assert_eq!(
+            parse_number::<u8>(" 256 "),
+            Err(AddressParseError::OutOfRange("256".to_string()))
+        );
+        assert_eq!(parse_number::<u8>(""), Err(AddressParseError::Empty));
+    }
+
+    #[test]
+    fn numbers_reject_segment_notation() {
+        assert_eq!(parse_address("0:5"), Ok(5));
+        assert_eq!(
+            parse_number::<u8>("0:5"),
+            Err(AddressParseError::InvalidNumber("0:5".to_string()))
+        );
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let mut a = MemoryMap::new();
+        a.insert(0x00, &[0; 0x10]);
+        a.insert(0x20, &[0; 0x10]);
+        a.insert(0x40, &[0; 4]);
+
+        let mut b = MemoryMap::new();
+        b.insert(0x08, &[0; 0x20]);
+        b.insert(0x2C, &[0; 0x20]);
+
+        assert_eq!(
+            a.overlapping_ranges(&b),
+            [0x08..0x10, 0x20..0x28, 0x2C..0x30, 0x40..0x44]
+        );
+        assert_eq!(b.overlapping_ranges(&a), a.overlapping_ranges(&b));
+
+        // Touching segments do not overlap
+        let mut c = MemoryMap::new();
+        c.insert(0x10, &[0; 0x10]);
+        assert!(a.overlapping_ranges(&c).is_empty());
+    }
+}
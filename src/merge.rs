@@ -0,0 +1,347 @@
+//! Combining the data of several files, such as a bootloader, an application and a
+//! configuration page, into a single file.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    memory::MemoryMap,
+    record::{
+        binary::{AddressingMode, BinaryImportError, BinaryImportOptions},
+        file::{IHexFile, StartAddr},
+    },
+};
+
+/// How bytes at addresses that are mapped in more than one input are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Any overlap between inputs is an error.
+    #[default]
+    Fail,
+    /// The byte of the earliest input is kept.
+    PreferFirst,
+    /// The byte of the latest input is kept.
+    PreferLast,
+    /// Overlap is allowed only where the inputs contain the same bytes.
+    AllowIdentical,
+}
+
+/// Options controlling how files are merged and how the merged file is generated.
+#[derive(Debug, Clone, Copy)]
+pub struct MergeOptions {
+    pub conflicts: ConflictPolicy,
+    /// The start address of the merged file. Defaults to the first start address
+    /// found among the inputs.
+    pub start_address: Option<StartAddr>,
+    /// The maximum amount of data bytes per data record.
+    pub record_size: u8,
+    pub addressing: AddressingMode,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            conflicts: ConflictPolicy::Fail,
+            start_address: None,
+            record_size: 16,
+            addressing: AddressingMode::Linear,
+        }
+    }
+}
+
+/// Checks the overlap between the inputs at `first` and `second` against `policy`
+fn check_overlap(
+    memories: &[MemoryMap],
+    first: usize,
+    second: usize,
+    policy: ConflictPolicy,
+) -> Result<(), MergeError> {
+    let (a, b) = (&memories[first], &memories[second]);
+
+    for range in a.overlapping_ranges(b) {
+        let start = range.start as u32;
+        let len = (range.end - range.start) as usize;
+
+        match policy {
+            ConflictPolicy::Fail => {
+                return Err(MergeError::Overlap {
+                    address: start,
+                    length: range.end - range.start,
+                    inputs: (first, second),
+                })
+            }
+            ConflictPolicy::AllowIdentical => {
+                let differing = a
+                    .read(start, len, 0)
+                    .iter()
+                    .zip(b.read(start, len, 0))
+                    .position(|(x, y)| *x != y);
+
+                if let Some(offset) = differing {
+                    return Err(MergeError::Conflict {
+                        address: start + offset as u32,
+                        inputs: (first, second),
+                    });
+                }
+            }
+            ConflictPolicy::PreferFirst | ConflictPolicy::PreferLast => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Merges the data of `files` into a single file, with the minimal amount of extended
+/// address records. Overlapping addresses are resolved according to `options.conflicts`.
+pub fn merge(files: &[IHexFile], options: &MergeOptions) -> Result<IHexFile, MergeError> {
+    let memories: Vec<MemoryMap> = files.iter().map(IHexFile::data_bytes).collect();
+
+    for second in 1..memories.len() {
+        for first in 0..second {
+            check_overlap(&memories, first, second, options.conflicts)?;
+        }
+    }
+
+    let mut merged = MemoryMap::new();
+    let mut insert = |memory: &MemoryMap| {
+        for segment in memory.segments() {
+            merged.insert(segment.start, &segment.data);
+        }
+    };
+
+    // Later inserts overwrite earlier ones, so the preferred input goes last
+    match options.conflicts {
+        ConflictPolicy::PreferFirst => memories.iter().rev().for_each(&mut insert),
+        _ => memories.iter().for_each(&mut insert),
+    }
+
+    let start_address = options
+        .start_address
+        .or_else(|| files.iter().find_map(IHexFile::start_address));
+
+    Ok(IHexFile::from_memory_map(
+        &merged,
+        &BinaryImportOptions {
+            record_size: options.record_size,
+            addressing: options.addressing,
+            start_address,
+        },
+    )?)
+}
+
+#[derive(Debug)]
+pub enum MergeError {
+    /// Two inputs, given by their 0-based indices, both map the range at `address`.
+    Overlap {
+        address: u32,
+        length: u64,
+        inputs: (usize, usize),
+    },
+    /// Two inputs, given by their 0-based indices, contain different bytes at `address`.
+    Conflict {
+        address: u32,
+        inputs: (usize, usize),
+    },
+    Import(BinaryImportError),
+}
+
+impl MergeError {
+    /// The 0-based indices of the inputs that conflict, if the error is caused by overlap.
+    pub fn inputs(&self) -> Option<(usize, usize)> {
+        match self {
+            MergeError::Overlap { inputs, .. } | MergeError::Conflict { inputs, .. } => {
+                Some(*inputs)
+            }
+            MergeError::Import(_) => None,
+        }
+    }
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::Overlap {
+                address,
+                length,
+                inputs: (first, second),
+            } => write!(
+                f,
+                "Inputs {} and {} overlap in {} bytes at 0x{:08X}",
+                first, second, length, address
+            ),
+            MergeError::Conflict {
+                address,
+                inputs: (first, second),
+            } => write!(
+                f,
+                "Inputs {} and {} contain different bytes at 0x{:08X}",
+                first, second, address
+            ),
+            MergeError::Import(e) => write!(f, "Could not generate the merged file: {}", e),
+        }
+    }
+}
+
+impl Error for MergeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MergeError::Import(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BinaryImportError> for MergeError {
+    fn from(e: BinaryImportError) -> Self {
+        MergeError::Import(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(segments: &[(u32, &[u8])], start_address: Option<StartAddr>) -> IHexFile {
+        let mut memory = MemoryMap::new();
+
+        for (start, data) in segments {
+            memory.insert(*start, data);
+        }
+
+        let options = BinaryImportOptions {
+            start_address,
+            ..Default::default()
+        };
+
+        IHexFile::from_memory_map(&memory, &options).unwrap()
+    }
+
+    fn merge_with(files: &[IHexFile], conflicts: ConflictPolicy) -> Result<Vec<u8>, MergeError> {
+        let options = MergeOptions {
+            conflicts,
+            ..Default::default()
+        };
+
+        merge(files, &options).map(|merged| merged.data_bytes().to_flat(0))
+    }
+
+    #[test]
+    fn disjoint_inputs() {
+        let files = [
+            file(&[(0x0800_0000, &[1, 2])], None),
+            file(&[(0x0800_4000, &[3])], Some(StartAddr::Linear(0x0800_4000))),
+            file(&[(0x0800_0002, &[4])], Some(StartAddr::Linear(0x0800_0002))),
+        ];
+        let merged = merge(&files, &MergeOptions::default()).unwrap();
+        let memory = merged.data_bytes();
+
+        assert_eq!(memory.segments().len(), 2);
+        assert_eq!(memory.read(0x0800_0000, 3, 0), [1, 2, 4]);
+        assert_eq!(memory.get(0x0800_4000), Some(3));
+
+        // The first start address among the inputs is kept, unless one is given
+        assert!(matches!(
+            merged.start_address(),
+            Some(StartAddr::Linear(0x0800_4000))
+        ));
+
+        let options = MergeOptions {
+            start_address: Some(StartAddr::Linear(0x0800_0000)),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            merge(&files, &options).unwrap().start_address(),
+            Some(StartAddr::Linear(0x0800_0000))
+        ));
+    }
+
+    #[test]
+    fn overlap_fails_by_default() {
+        let files = [
+            file(&[(0, &[1, 2, 3, 4])], None),
+            file(&[(0x10, &[5])], None),
+            file(&[(2, &[3, 4, 5])], None),
+        ];
+        let error = merge_with(&files, ConflictPolicy::Fail).unwrap_err();
+
+        assert!(matches!(
+            error,
+            MergeError::Overlap {
+                address: 2,
+                length: 2,
+                inputs: (0, 2)
+            }
+        ));
+        assert_eq!(error.inputs(), Some((0, 2)));
+
+        // Touching inputs do not overlap
+        let files = [file(&[(0, &[1, 2])], None), file(&[(2, &[3])], None)];
+        assert_eq!(merge_with(&files, ConflictPolicy::Fail).unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn preferred_input_wins() {
+        let files = [
+            file(&[(0, &[1, 2, 3, 4])], None),
+            file(&[(2, &[9, 9, 9])], None),
+            file(&[(4, &[7, 7])], None),
+        ];
+
+        assert_eq!(
+            merge_with(&files, ConflictPolicy::PreferFirst).unwrap(),
+            [1, 2, 3, 4, 9, 7]
+        );
+        assert_eq!(
+            merge_with(&files, ConflictPolicy::PreferLast).unwrap(),
+            [1, 2, 9, 9, 7, 7]
+        );
+    }
+
+    #[test]
+    fn identical_overlap() {
+        let files = [
+            file(&[(0, &[1, 2, 3, 4])], None),
+            file(&[(2, &[3, 4, 5])], None),
+        ];
+
+        assert_eq!(
+            merge_with(&files, ConflictPolicy::AllowIdentical).unwrap(),
+            [1, 2, 3, 4, 5]
+        );
+
+        let files = [
+            file(&[(0, &[1, 2, 3, 4])], None),
+            file(&[(8, &[0])], None),
+            file(&[(2, &[3, 9])], None),
+        ];
+        let error = merge_with(&files, ConflictPolicy::AllowIdentical).unwrap_err();
+
+        // The first differing byte is reported, not the start of the overlap
+        assert!(matches!(
+            error,
+            MergeError::Conflict {
+                address: 3,
+                inputs: (0, 2)
+            }
+        ));
+    }
+
+    #[test]
+    fn merged_data_must_fit_addressing() {
+        let files = [file(&[(0x0010_0000, &[1])], None)];
+        let options = MergeOptions {
+            addressing: AddressingMode::Segment,
+            ..Default::default()
+        };
+        let error = merge(&files, &options).unwrap_err();
+
+        assert!(matches!(
+            error,
+            MergeError::Import(BinaryImportError::AddressOutOfRange)
+        ));
+        assert_eq!(error.inputs(), None);
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::memory::MemoryMap;
+
+use super::{
+    file::{IHexFile, SegmentStartAddr, StartAddr},
+    DataRecord, EndOfFileRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord,
+    IHexRecord, StartLinearAddressRecord, StartSegmentAddressRecord,
+};
+
+/// The kind of extended address records used when data crosses a 64 KiB boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressingMode {
+    /// Extended Segment Address records, limited to a 1 MiB address space.
+    Segment,
+    /// Extended Linear Address records, covering the full 32-bit address space.
+    #[default]
+    Linear,
+}
+
+impl AddressingMode {
+    const fn address_limit(&self) -> u64 {
+        match self {
+            AddressingMode::Segment => 1 << 20,
+            AddressingMode::Linear => 1 << 32,
+        }
+    }
+
+    /// The start address pointing at the linear `address`. Segment start addresses use
+    /// the lowest 4 bits of the address as the instruction pointer.
+    pub fn start_address(&self, address: u32) -> Result<StartAddr, BinaryImportError> {
+        match self {
+            AddressingMode::Segment => {
+                if address as u64 >= self.address_limit() {
+                    return Err(BinaryImportError::AddressOutOfRange);
+                }
+
+                Ok(StartAddr::Segment(SegmentStartAddr {
+                    code_segment: (address >> 4) as u16,
+                    instruction_pointer: (address & 0xF) as u16,
+                }))
+            }
+            AddressingMode::Linear => Ok(StartAddr::Linear(address)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryImportOptions {
+    /// The maximum amount of data bytes per data record.
+    pub record_size: u8,
+    pub addressing: AddressingMode,
+    pub start_address: Option<StartAddr>,
+}
+
+impl Default for BinaryImportOptions {
+    fn default() -> Self {
+        BinaryImportOptions {
+            record_size: 16,
+            addressing: AddressingMode::Linear,
+            start_address: None,
+        }
+    }
+}
+
+/// Builds a sequence of data records, inserting extended address records
+/// whenever the data crosses into a different 64 KiB region.
+pub(super) struct RecordBuilder {
+    options: BinaryImportOptions,
+    /// The 64 KiB region the data records are currently in, if known
+    region: Option<u32>,
+    records: Vec<IHexRecord>,
+}
+
+impl RecordBuilder {
+    pub(super) fn new(options: &BinaryImportOptions) -> Result<Self, BinaryImportError> {
+        Self::continuing(options, Some(0))
+    }
+
+    /// Creates a builder for records that follow existing records, which left the data in
+    /// `region`. With `None`, the first data record is always preceded by an extended
+    /// address record.
+    pub(super) fn continuing(
+        options: &BinaryImportOptions,
+        region: Option<u32>,
+    ) -> Result<Self, BinaryImportError> {
+        if options.record_size == 0 {
+            return Err(BinaryImportError::InvalidRecordSize);
+        }
+
+        Ok(RecordBuilder {
+            options: *options,
+            region,
+            records: Vec::new(),
+        })
+    }
+
+    fn enter_region(&mut self, region: u32) {
+        if self.region == Some(region) {
+            return;
+        }
+
+        let record = match self.options.addressing {
+            AddressingMode::Segment => {
+                IHexRecord::ExtendedSegmentAddress(ExtendedSegmentAddressRecord {
+                    segment_base: (region as usize) << 16,
+                })
+            }
+            AddressingMode::Linear => {
+                IHexRecord::ExtendedLinearAddress(ExtendedLinearAddressRecord {
+                    address_base: region as u16,
+                })
+            }
+        };
+
+        self.records.push(record);
+        self.region = Some(region);
+    }
+
+    /// Appends data records for a contiguous block of bytes starting at `address`.
+    pub(super) fn push_block(
+        &mut self,
+        address: u32,
+        data: &[u8],
+    ) -> Result<(), BinaryImportError> {
+        let end = address as u64 + data.len() as u64;
+
+        if end > self.options.addressing.address_limit() {
+            return Err(BinaryImportError::AddressOutOfRange);
+        }
+
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let current = address + offset as u32;
+            let region_remaining = 0x10000 - (current & 0xFFFF) as usize;
+            let chunk_len = (self.options.record_size as usize)
+                .min(region_remaining)
+                .min(data.len() - offset);
+
+            self.enter_region(current >> 16);
+            self.records.push(IHexRecord::Data(DataRecord::new(
+                (current & 0xFFFF) as u16,
+                data[offset..offset + chunk_len].to_vec(),
+            )));
+
+            offset += chunk_len;
+        }
+
+        Ok(())
+    }
+
+    /// The records built so far, without a start address or End of File record.
+    pub(super) fn into_records(self) -> Vec<IHexRecord> {
+        self.records
+    }
+
+    /// Appends the start address (if any) and the End of File record, and builds the file.
+    pub(super) fn finish(mut self) -> IHexFile {
+        match self.options.start_address {
+            Some(StartAddr::Segment(addr)) => {
+                self.records
+                    .push(IHexRecord::StartSegmentAddress(StartSegmentAddressRecord {
+                        code_segment: addr.code_segment,
+                        instruction_pointer: addr.instruction_pointer,
+                    }))
+            }
+            Some(StartAddr::Linear(entry_point)) => {
+                self.records
+                    .push(IHexRecord::StartLinearAddress(StartLinearAddressRecord {
+                        entry_point,
+                    }))
+            }
+            None => {}
+        }
+
+        self.records
+            .push(IHexRecord::EndOfFile(EndOfFileRecord::default()));
+
+        IHexFile::from_records(self.records)
+    }
+}
+
+impl IHexFile {
+    /// Generates a file containing `data`, loaded at `base_address`.
+    pub fn from_binary(
+        data: &[u8],
+        base_address: u32,
+        options: &BinaryImportOptions,
+    ) -> Result<Self, BinaryImportError> {
+        let mut builder = RecordBuilder::new(options)?;
+
+        builder.push_block(base_address, data)?;
+
+        Ok(builder.finish())
+    }
+
+    /// Generates a file containing every segment of `memory`, in address order.
+    pub fn from_memory_map(
+        memory: &MemoryMap,
+        options: &BinaryImportOptions,
+    ) -> Result<Self, BinaryImportError> {
+        let mut builder = RecordBuilder::new(options)?;
+
+        for segment in memory.segments() {
+            builder.push_block(segment.start, &segment.data)?;
+        }
+
+        Ok(builder.finish())
+    }
+}
+
+#[derive(Debug)]
+pub enum BinaryImportError {
+    InvalidRecordSize,
+    AddressOutOfRange,
+}
+
+impl Display for BinaryImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryImportError::InvalidRecordSize => {
+                write!(f, "Record size must be at least one byte")
+            }
+            BinaryImportError::AddressOutOfRange => {
+                write!(
+                    f,
+                    "Data does not fit in the address space of the addressing mode"
+                )
+            }
+        }
+    }
+}
+
+impl Error for BinaryImportError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::file::{IHexFileType, IHexWriteOptions, SegmentStartAddr};
+
+    fn write(file: &IHexFile) -> String {
+        let mut buf = Vec::new();
+        file.write(&mut buf, &IHexWriteOptions::default()).unwrap();
+
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn linear_addressing() {
+        let data: Vec<u8> = (0..20).collect();
+        let options = BinaryImportOptions {
+            record_size: 8,
+            addressing: AddressingMode::Linear,
+            start_address: Some(StartAddr::Linear(0x1_FFF8)),
+        };
+        let file = IHexFile::from_binary(&data, 0x1_FFF8, &options).unwrap();
+
+        // Records are split at the 64 KiB boundary, which gets a new base address
+        assert_eq!(
+            write(&file),
+            ":020000040001F9\n:08FFF8000001020304050607E5\n:020000040002F8\n:0800000008090A0B0C0D0E0F9C\n:0400080010111213AE\n:040000050001FFF8FF\n:00000001FF\n"
+        );
+        assert_eq!(file.filetype(), IHexFileType::IHex32);
+        assert!(matches!(
+            file.start_address(),
+            Some(StartAddr::Linear(0x1_FFF8))
+        ));
+    }
+
+    #[test]
+    fn segment_addressing() {
+        let data: Vec<u8> = (0..8).collect();
+        let options = BinaryImportOptions {
+            record_size: 16,
+            addressing: AddressingMode::Segment,
+            start_address: Some(StartAddr::Segment(SegmentStartAddr {
+                code_segment: 0xEFFF,
+                instruction_pointer: 0x000C,
+            })),
+        };
+        let file = IHexFile::from_binary(&data, 0xE_FFFC, &options).unwrap();
+
+        assert_eq!(
+            write(&file),
+            ":02000002E0001C\n:04FFFC0000010203FB\n:02000002F0000C\n:0400000004050607E6\n:04000003EFFF000CFF\n:00000001FF\n"
+        );
+        assert_eq!(file.filetype(), IHexFileType::IHex16);
+    }
+
+    #[test]
+    fn data_in_first_region() {
+        let file =
+            IHexFile::from_binary(&[0xAA; 3], 0x10, &BinaryImportOptions::default()).unwrap();
+
+        // No extended address record is needed below 64 KiB
+        assert_eq!(write(&file), ":03001000AAAAAAEF\n:00000001FF\n");
+        assert_eq!(file.filetype(), IHexFileType::IHex8);
+
+        let file = IHexFile::from_binary(&[], 0, &BinaryImportOptions::default()).unwrap();
+        assert_eq!(write(&file), ":00000001FF\n");
+    }
+
+    #[test]
+    fn import_errors() {
+        let options = BinaryImportOptions {
+            record_size: 0,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            IHexFile::from_binary(&[1], 0, &options),
+            Err(BinaryImportError::InvalidRecordSize)
+        ));
+
+        // The last byte of the address space can be used, but not the byte after it
+        assert!(IHexFile::from_binary(&[1], 0xFFFF_FFFF, &BinaryImportOptions::default()).is_ok());
+        assert!(matches!(
+            IHexFile::from_binary(&[1, 2], 0xFFFF_FFFF, &BinaryImportOptions::default()),
+            Err(BinaryImportError::AddressOutOfRange)
+        ));
+
+        let options = BinaryImportOptions {
+            addressing: AddressingMode::Segment,
+            ..Default::default()
+        };
+
+        assert!(IHexFile::from_binary(&[1], 0xF_FFFF, &options).is_ok());
+        assert!(matches!(
+            IHexFile::from_binary(&[1, 2], 0xF_FFFF, &options),
+            Err(BinaryImportError::AddressOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn from_memory_map() {
+        let mut memory = MemoryMap::new();
+        memory.insert(0x0010_0000, &[4]);
+        memory.insert(0x20, &[3]);
+        memory.insert(0x10, &[1, 2]);
+
+        let file = IHexFile::from_memory_map(&memory, &BinaryImportOptions::default()).unwrap();
+
+        assert_eq!(
+            write(&file),
+            ":020010000102EB\n:0100200003DC\n:020000040010EA\n:0100000004FB\n:00000001FF\n"
+        );
+        assert_eq!(file.data_bytes(), memory);
+    }
+}
@@ -1,15 +1,17 @@
-use anyhow::Result;
 use std::{
     fmt::Display,
-    io::{BufRead, Lines},
+    io::{BufRead, Lines, Write},
 };
 
-use crate::{to_u16_be, to_u32_be};
+use crate::{
+    memory::MemoryMap,
+    utils::{to_u16_be, to_u32_be},
+};
 
 use super::{
-    raw::{parse_ihex, RawIHexRecord},
-    DataRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord, IHexRecord,
-    StartLinearAddressRecord, StartSegmentAddressRecord,
+    raw::{parse_ihex, HexCase, IHexParseError, RawIHexRecord},
+    DataRecord, EndOfFileRecord, ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord,
+    IHexRecord, StartLinearAddressRecord, StartSegmentAddressRecord,
 };
 
 macro_rules! expect_length {
@@ -25,18 +27,54 @@ struct BaseAddrs {
     linear: Option<ExtendedLinearAddressRecord>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SegmentStartAddr {
     pub code_segment: u16,
     pub instruction_pointer: u16,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StartAddr {
     Segment(SegmentStartAddr),
     Linear(u32),
 }
 
+impl StartAddr {
+    /// The 32-bit address execution starts at. Segment start addresses are
+    /// converted as `(CS << 4) + IP`.
+    pub fn linear_address(&self) -> u32 {
+        match self {
+            StartAddr::Segment(addr) => {
+                ((addr.code_segment as u32) << 4) + addr.instruction_pointer as u32
+            }
+            StartAddr::Linear(address) => *address,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Options controlling how an [`IHexFile`] is serialized back to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IHexWriteOptions {
+    pub case: HexCase,
+    pub line_ending: LineEnding,
+}
+
 #[derive(Debug)]
 pub struct IHexFile {
     pub records: Vec<IHexRecord>,
@@ -90,25 +128,115 @@ impl IHexFile {
         filetype
     }
 
-    pub fn read<T: BufRead>(lines: Lines<T>) -> Result<Self> {
-        let mut raw_records = Vec::new();
+    /// Creates a file from a list of records, assigning base addresses to
+    /// the data records and determining the filetype and start address.
+    pub fn from_records(mut records: Vec<IHexRecord>) -> Self {
+        let mut bases = BaseAddrs {
+            segment: None,
+            linear: None,
+        };
 
-        for line in lines {
-            let line = line?;
-            let record = parse_ihex(&line)?;
-            raw_records.push(record);
+        let mut start_addr: Option<StartAddr> = None;
+
+        for record in records.iter_mut() {
+            match record {
+                IHexRecord::Data(data) => {
+                    data.segment_address = bases.segment.clone();
+                    data.linear_address = bases.linear.clone();
+                }
+                IHexRecord::ExtendedSegmentAddress(addr) => bases.segment = Some(addr.clone()),
+                IHexRecord::ExtendedLinearAddress(addr) => bases.linear = Some(addr.clone()),
+                IHexRecord::StartSegmentAddress(addr) => {
+                    start_addr.get_or_insert(StartAddr::Segment(SegmentStartAddr {
+                        code_segment: addr.code_segment,
+                        instruction_pointer: addr.instruction_pointer,
+                    }));
+                }
+                IHexRecord::StartLinearAddress(addr) => {
+                    start_addr.get_or_insert(StartAddr::Linear(addr.entry_point));
+                }
+                IHexRecord::EndOfFile(_) => {}
+            }
         }
 
+        IHexFile {
+            filetype: Self::determine_filetype(&records),
+            records,
+            start_address: start_addr,
+        }
+    }
+
+    /// Recalculates the base addresses of all data records, the filetype and the
+    /// start address. Must be called after modifying [`IHexFile::records`].
+    pub fn refresh(&mut self) {
+        *self = Self::from_records(std::mem::take(&mut self.records));
+    }
+
+    /// Reads a file, aborting on the first invalid record.
+    pub fn read<T: BufRead>(lines: Lines<T>) -> Result<Self, IHexReadError> {
+        let (file, mut diagnostics) = Self::read_impl(lines, false);
+
+        match diagnostics.pop() {
+            Some(diagnostic) => Err(diagnostic.error),
+            None => Ok(file),
+        }
+    }
+
+    /// Reads a file, skipping invalid records instead of aborting.
+    /// Returns the file containing all valid records, along with a diagnostic for each
+    /// record that could not be read.
+    pub fn read_lenient<T: BufRead>(lines: Lines<T>) -> (Self, Vec<IHexDiagnostic>) {
+        Self::read_impl(lines, true)
+    }
+
+    fn read_impl<T: BufRead>(lines: Lines<T>, lenient: bool) -> (Self, Vec<IHexDiagnostic>) {
         let mut bases = BaseAddrs {
             segment: None,
             linear: None,
         };
 
-        let mut records = Vec::with_capacity(raw_records.len());
+        let mut records = Vec::new();
+        let mut diagnostics = Vec::new();
         let mut start_addr: Option<StartAddr> = None;
 
-        for raw_record in raw_records {
-            Self::parse_and_append(&mut records, raw_record, &mut bases, &mut start_addr)?;
+        for (i, line) in lines.enumerate() {
+            let line_number = i + 1;
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    // The underlying reader is broken, so there is nothing left to recover
+                    diagnostics.push(IHexDiagnostic {
+                        record_index: records.len(),
+                        error: IHexReadError::new(line_number, "", IHexReadErrorKind::Io(e)),
+                    });
+                    break;
+                }
+            };
+
+            let result =
+                parse_ihex(&line)
+                    .map_err(IHexReadErrorKind::from)
+                    .and_then(|raw_record| {
+                        Self::parse_and_append(
+                            &mut records,
+                            raw_record,
+                            &mut bases,
+                            &mut start_addr,
+                        )
+                        .map_err(IHexReadErrorKind::from)
+                    });
+
+            if let Err(kind) = result {
+                diagnostics.push(IHexDiagnostic {
+                    record_index: records.len(),
+                    error: IHexReadError::new(line_number, &line, kind),
+                });
+
+                if !lenient {
+                    break;
+                }
+            }
         }
 
         let ihex_file = IHexFile {
@@ -117,31 +245,67 @@ impl IHexFile {
             start_address: start_addr,
         };
 
-        Ok(ihex_file)
+        (ihex_file, diagnostics)
     }
 
-    pub fn data_bytes(&self) -> Vec<u8> {
-        let records: Vec<_> = self
-            .records
-            .iter()
-            .filter_map(|record| match record {
-                IHexRecord::Data(data_record) => Some(data_record),
-                _ => None,
-            })
-            .collect();
+    /// Serializes all records in the file, one record per line.
+    /// Checksums are always recalculated from the record contents.
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if a data record holds more than 255 bytes.
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &IHexWriteOptions,
+    ) -> std::io::Result<()> {
+        for record in &self.records {
+            let raw = RawIHexRecord::try_from(record)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+            writer.write_all(raw.to_ihex_string(options.case).as_bytes())?;
+            writer.write_all(options.line_ending.as_str().as_bytes())?;
+        }
 
-        let mut data = Vec::new();
+        Ok(())
+    }
 
-        for record in records {
-            let effective_addr = record.calc_effective_address();
+    /// Serializes the file to a string. Fails like [`IHexFile::write`].
+    pub fn to_ihex_string(&self, options: &IHexWriteOptions) -> std::io::Result<String> {
+        let mut buf = Vec::new();
+
+        self.write(&mut buf, options)?;
+
+        Ok(String::from_utf8(buf).expect("Intel HEX output is always ASCII"))
+    }
+
+    /// Builds the memory image described by the data records in this file.
+    /// Where data records overlap, later records overwrite earlier ones.
+    pub fn data_bytes(&self) -> MemoryMap {
+        let mut memory = MemoryMap::new();
 
-            data.resize(effective_addr as usize + record.data.len(), 0);
-            for (i, byte) in record.data.iter().enumerate() {
-                data[effective_addr as usize + i] = *byte;
+        for record in self.records.iter() {
+            if let IHexRecord::Data(data_record) = record {
+                memory.insert(data_record.calc_effective_address(), &data_record.data);
             }
         }
 
-        data
+        memory
+    }
+
+    /// The index of the data record that provides the byte at `address` in
+    /// [`IHexFile::data_bytes`], which is the last data record covering it.
+    pub fn record_at_address(&self, address: u32) -> Option<usize> {
+        self.records
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, record)| match record {
+                IHexRecord::Data(data) => {
+                    let start = data.calc_effective_address() as u64;
+                    let end = start + data.data.len() as u64;
+
+                    (start..end).contains(&(address as u64)).then_some(i)
+                }
+                _ => None,
+            })
     }
 
     fn parse_and_append(
@@ -151,7 +315,10 @@ impl IHexFile {
         start_addr: &mut Option<StartAddr>,
     ) -> Result<(), InvalidIHexRecordError> {
         if !value.checksum_valid() {
-            return Err(InvalidIHexRecordError::Checksum);
+            return Err(InvalidIHexRecordError::Checksum {
+                expected: value.generate_checksum(),
+                found: value.checksum,
+            });
         }
 
         let rec = match value.rectyp {
@@ -161,7 +328,7 @@ impl IHexFile {
                 naive_address: value.load_offset,
                 data: value.data,
             }),
-            1 => IHexRecord::EndOfFile,
+            1 => IHexRecord::EndOfFile(EndOfFileRecord { data: value.data }),
             2 => {
                 expect_length!(value.data, 2);
                 let segment_base = to_u16_be!(value.data);
@@ -229,7 +396,7 @@ impl IHexFile {
 
 #[derive(Debug)]
 pub enum InvalidIHexRecordError {
-    Checksum,
+    Checksum { expected: u8, found: u8 },
     RecordType,
     InvalidDataSizeForType,
 }
@@ -237,7 +404,11 @@ pub enum InvalidIHexRecordError {
 impl Display for InvalidIHexRecordError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            InvalidIHexRecordError::Checksum => write!(f, "Invalid checksum"),
+            InvalidIHexRecordError::Checksum { expected, found } => write!(
+                f,
+                "Invalid checksum (expected 0x{:02X}, found 0x{:02X})",
+                expected, found
+            ),
             InvalidIHexRecordError::RecordType => write!(f, "Invalid record type"),
             InvalidIHexRecordError::InvalidDataSizeForType => {
                 write!(f, "Invalid data size for record type")
@@ -247,3 +418,286 @@ impl Display for InvalidIHexRecordError {
 }
 
 impl std::error::Error for InvalidIHexRecordError {}
+
+#[derive(Debug)]
+pub enum IHexReadErrorKind {
+    Io(std::io::Error),
+    Parse(IHexParseError),
+    Record(InvalidIHexRecordError),
+}
+
+impl From<IHexParseError> for IHexReadErrorKind {
+    fn from(e: IHexParseError) -> Self {
+        IHexReadErrorKind::Parse(e)
+    }
+}
+
+impl From<InvalidIHexRecordError> for IHexReadErrorKind {
+    fn from(e: InvalidIHexRecordError) -> Self {
+        IHexReadErrorKind::Record(e)
+    }
+}
+
+impl Display for IHexReadErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IHexReadErrorKind::Io(e) => write!(f, "I/O error: {}", e),
+            IHexReadErrorKind::Parse(e) => write!(f, "{}", e),
+            IHexReadErrorKind::Record(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// An error encountered while reading a file, along with its location.
+#[derive(Debug)]
+pub struct IHexReadError {
+    /// The 1-based line number of the offending record.
+    pub line: usize,
+    /// The 1-based column at which the error was detected, if known.
+    pub column: Option<usize>,
+    /// The text of the offending record.
+    pub record: String,
+    pub kind: IHexReadErrorKind,
+}
+
+impl IHexReadError {
+    fn new(line: usize, record: &str, kind: IHexReadErrorKind) -> Self {
+        IHexReadError {
+            line,
+            column: Self::error_column(record, &kind),
+            record: record.to_string(),
+            kind,
+        }
+    }
+
+    fn error_column(record: &str, kind: &IHexReadErrorKind) -> Option<usize> {
+        let column = match kind {
+            IHexReadErrorKind::Io(_) => return None,
+            IHexReadErrorKind::Parse(e) => match e {
+                IHexParseError::MissingStartCode => 1,
+                IHexParseError::NonAsciiString => record.chars().position(|c| !c.is_ascii())? + 1,
+                IHexParseError::NonHexString => {
+                    match record[1..].chars().position(|c| !c.is_ascii_hexdigit()) {
+                        Some(pos) => pos + 2,
+                        None => record.len(), // Odd amount of hex digits
+                    }
+                }
+                IHexParseError::RecordTooShort => record.len() + 1,
+                IHexParseError::IncorrectDataSize => 2,
+            },
+            IHexReadErrorKind::Record(e) => match e {
+                InvalidIHexRecordError::Checksum { .. } => record.len() - 1,
+                InvalidIHexRecordError::RecordType => 8,
+                InvalidIHexRecordError::InvalidDataSizeForType => 2,
+            },
+        };
+
+        Some(column)
+    }
+}
+
+impl Display for IHexReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "Line {}, column {}: {}", self.line, column, self.kind)?,
+            None => write!(f, "Line {}: {}", self.line, self.kind)?,
+        }
+
+        if !self.record.is_empty() {
+            write!(f, " (record: \"{}\")", self.record)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A record that was skipped while leniently reading a file.
+#[derive(Debug)]
+pub struct IHexDiagnostic {
+    /// The index in [`IHexFile::records`] at which the skipped record would have been placed.
+    pub record_index: usize,
+    pub error: IHexReadError,
+}
+
+impl std::error::Error for IHexReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            IHexReadErrorKind::Io(e) => Some(e),
+            IHexReadErrorKind::Parse(e) => Some(e),
+            IHexReadErrorKind::Record(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+
+    /// Every record type, with both segment and linear addressing
+    const EXAMPLE: &str = ":020000021000EC
+:0400000048656C6C77
+:020000040001F9
+:10001000000102030405060708090A0B0C0D0E0F68
+:0400000500001234B1
+:0400000310000010D9
+:00000001FF
+";
+
+    fn read(text: &str) -> IHexFile {
+        IHexFile::read(text.as_bytes().lines()).unwrap()
+    }
+
+    fn write(file: &IHexFile, options: &IHexWriteOptions) -> String {
+        let mut buf = Vec::new();
+        file.write(&mut buf, options).unwrap();
+
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn read_error(text: &str) -> IHexReadError {
+        IHexFile::read(text.as_bytes().lines()).unwrap_err()
+    }
+
+    #[test]
+    fn write_reproduces_input() {
+        let file = read(EXAMPLE);
+
+        assert_eq!(write(&file, &IHexWriteOptions::default()), EXAMPLE);
+        assert_eq!(
+            read(&write(&file, &IHexWriteOptions::default())).records,
+            file.records
+        );
+    }
+
+    #[test]
+    fn write_options() {
+        let file = read(":0400000048656C6C77\n:00000001FF\n");
+        let options = IHexWriteOptions {
+            case: HexCase::Lower,
+            line_ending: LineEnding::CrLf,
+        };
+
+        assert_eq!(
+            write(&file, &options),
+            ":0400000048656c6c77\r\n:00000001ff\r\n"
+        );
+
+        // Lowercase files are read back the same
+        assert_eq!(read(&write(&file, &options)).records, file.records);
+    }
+
+    #[test]
+    fn write_recalculates_checksums() {
+        let mut file = read(":0400000048656C6C77\n:00000001FF\n");
+
+        if let IHexRecord::Data(record) = &mut file.records[0] {
+            record.naive_address = 0x0100;
+            record.data[0] = b'J';
+        }
+
+        assert_eq!(
+            write(&file, &IHexWriteOptions::default()),
+            ":040100004A656C6C74\n:00000001FF\n"
+        );
+
+        // The record length follows the data
+        if let IHexRecord::Data(record) = &mut file.records[0] {
+            record.data.extend_from_slice(b"oW");
+        }
+
+        assert!(write(&file, &IHexWriteOptions::default()).starts_with(":060100004A656C6C6F57AC\n"));
+    }
+
+    #[test]
+    fn write_oversized_record() {
+        let mut file = read(":0400000048656C6C77\n:00000001FF\n");
+
+        if let IHexRecord::Data(record) = &mut file.records[0] {
+            record.data = vec![0xAA; 255];
+        }
+
+        assert!(write(&file, &IHexWriteOptions::default()).starts_with(":FF000000AAAA"));
+
+        if let IHexRecord::Data(record) = &mut file.records[0] {
+            record.data.push(0xAA);
+        }
+
+        let error = file
+            .write(&mut Vec::new(), &IHexWriteOptions::default())
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(file.to_ihex_string(&IHexWriteOptions::default()).is_err());
+    }
+
+    #[test]
+    fn error_locations() {
+        let error = read_error(":00000001FF\n0400000048656C6C77\n");
+        assert_eq!((error.line, error.column), (2, Some(1)));
+        assert!(matches!(
+            error.kind,
+            IHexReadErrorKind::Parse(IHexParseError::MissingStartCode)
+        ));
+        assert_eq!(error.record, "0400000048656C6C77");
+
+        let error = read_error(":04000000486G6C6C77\n");
+        assert_eq!((error.line, error.column), (1, Some(13)));
+
+        // An odd amount of hex digits is reported at the last digit
+        let error = read_error(":0400000048656C6C7\n");
+        assert_eq!(error.column, Some(18));
+
+        let error = read_error(":0400000048656C6C78\n");
+        assert_eq!(error.column, Some(18));
+        assert!(matches!(
+            error.kind,
+            IHexReadErrorKind::Record(InvalidIHexRecordError::Checksum {
+                expected: 0x77,
+                found: 0x78
+            })
+        ));
+
+        let error = read_error(":0400000748656C6C70\n");
+        assert_eq!(error.column, Some(8));
+        assert!(matches!(
+            error.kind,
+            IHexReadErrorKind::Record(InvalidIHexRecordError::RecordType)
+        ));
+
+        let error = read_error(":0000\n");
+        assert_eq!(error.column, Some(6));
+
+        let error = read_error(":05000000AABB9F\n");
+        assert_eq!(error.column, Some(2));
+
+        assert_eq!(
+            read_error("\n:00000001FF\n:0000\n").to_string(),
+            "Line 1, column 1: Record is missing start code"
+        );
+    }
+
+    #[test]
+    fn lenient_read_collects_errors() {
+        let text = ":0400000048656C6C77
+garbage
+:0400040048656C6C73
+:0400080048656C6C78
+:00000001FF
+";
+
+        assert_eq!(read_error(text).line, 2);
+
+        let (file, diagnostics) = IHexFile::read_lenient(text.as_bytes().lines());
+        let skipped: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.record_index, diagnostic.error.line))
+            .collect();
+
+        assert_eq!(skipped, [(1, 2), (2, 4)]);
+        assert_eq!(file.records.len(), 3);
+        assert_eq!(file.data_bytes().len(), 8);
+        assert_eq!(file.data_bytes().read(4, 4, 0), b"Hell");
+    }
+}
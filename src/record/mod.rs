@@ -1,5 +1,8 @@
+pub mod binary;
 pub mod file;
+mod patch;
 pub mod raw;
+pub mod validate;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DataRecord {
@@ -10,6 +13,18 @@ pub struct DataRecord {
 }
 
 impl DataRecord {
+    /// Creates a data record that is not yet associated with any base address.
+    /// Base addresses are assigned when the record is placed in an
+    /// [`IHexFile`](file::IHexFile).
+    pub fn new(naive_address: u16, data: Vec<u8>) -> Self {
+        DataRecord {
+            segment_address: None,
+            linear_address: None,
+            naive_address,
+            data,
+        }
+    }
+
     pub fn calc_effective_address(&self) -> u32 {
         let linear_base: u32 = match &self.linear_address {
             Some(linear) => (linear.address_base as u32) << 16,
@@ -25,6 +40,12 @@ impl DataRecord {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EndOfFileRecord {
+    /// The data of the record. A valid End of File record has none, but it is kept
+    /// so that it can be reported and written back.
+    pub data: Vec<u8>,
+}
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExtendedSegmentAddressRecord {
     pub segment_base: usize,
@@ -46,7 +67,7 @@ pub struct StartLinearAddressRecord {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IHexRecord {
     Data(DataRecord),
-    EndOfFile,
+    EndOfFile(EndOfFileRecord),
     ExtendedSegmentAddress(ExtendedSegmentAddressRecord),
     StartSegmentAddress(StartSegmentAddressRecord),
     ExtendedLinearAddress(ExtendedLinearAddressRecord),
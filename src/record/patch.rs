@@ -0,0 +1,233 @@
+use super::{
+    binary::{AddressingMode, BinaryImportError, BinaryImportOptions, RecordBuilder},
+    file::{IHexFile, IHexFileType},
+    ExtendedLinearAddressRecord, ExtendedSegmentAddressRecord, IHexRecord,
+};
+
+/// The start and length of every run of consecutive addresses in `address..address + len`
+/// that no data record covers
+fn unmapped_runs(file: &IHexFile, address: u32, len: usize) -> Vec<(u32, usize)> {
+    let mut runs: Vec<(u32, usize)> = Vec::new();
+
+    for offset in 0..len {
+        let current = address + offset as u32;
+
+        if file.record_at_address(current).is_some() {
+            continue;
+        }
+
+        match runs.last_mut() {
+            Some((start, run_len)) if *start + *run_len as u32 == current => *run_len += 1,
+            _ => runs.push((current, 1)),
+        }
+    }
+
+    runs
+}
+
+impl IHexFile {
+    /// Generates a copy of the file with `data` written at `address`.
+    ///
+    /// Bytes that are already mapped are changed in the data records that provide them.
+    /// Unmapped bytes are added as new data records of at most `record_size` bytes, right
+    /// before the End of File record, along with the extended address records they need.
+    pub fn with_bytes(
+        &self,
+        address: u32,
+        data: &[u8],
+        record_size: u8,
+    ) -> Result<IHexFile, BinaryImportError> {
+        let end = address as u64 + data.len() as u64;
+
+        if end > 1 << 32 {
+            return Err(BinaryImportError::AddressOutOfRange);
+        }
+
+        let mut records = self.records.clone();
+
+        for (offset, byte) in data.iter().enumerate() {
+            let current = address + offset as u32;
+
+            if let Some(index) = self.record_at_address(current) {
+                if let IHexRecord::Data(record) = &mut records[index] {
+                    let record_offset = current - record.calc_effective_address();
+                    record.data[record_offset as usize] = *byte;
+                }
+            }
+        }
+
+        let runs = unmapped_runs(self, address, data.len());
+
+        if runs.is_empty() {
+            return Ok(IHexFile::from_records(records));
+        }
+
+        let insert_at = records
+            .iter()
+            .position(|record| matches!(record, IHexRecord::EndOfFile(_)))
+            .unwrap_or(records.len());
+
+        // The base addresses in effect at the insertion point
+        let (mut segment_base, mut linear_base) = (0, 0);
+
+        for record in records[..insert_at].iter() {
+            match record {
+                IHexRecord::ExtendedSegmentAddress(addr) => segment_base = addr.segment_base,
+                IHexRecord::ExtendedLinearAddress(addr) => linear_base = addr.address_base,
+                _ => {}
+            }
+        }
+
+        // Segment files keep using segment addressing, as long as the data fits
+        let addressing = match self.filetype() == IHexFileType::IHex16 && end <= 1 << 20 {
+            true => AddressingMode::Segment,
+            false => AddressingMode::Linear,
+        };
+
+        let mut new_records = Vec::new();
+
+        // Reset the base of the other addressing mode, which would otherwise be added
+        let region = match addressing {
+            AddressingMode::Segment => {
+                if linear_base != 0 {
+                    new_records.push(IHexRecord::ExtendedLinearAddress(
+                        ExtendedLinearAddressRecord { address_base: 0 },
+                    ));
+                }
+
+                (segment_base % 0x10000 == 0).then_some((segment_base >> 16) as u32)
+            }
+            AddressingMode::Linear => {
+                if segment_base != 0 {
+                    new_records.push(IHexRecord::ExtendedSegmentAddress(
+                        ExtendedSegmentAddressRecord { segment_base: 0 },
+                    ));
+                }
+
+                Some(linear_base as u32)
+            }
+        };
+
+        let options = BinaryImportOptions {
+            record_size,
+            addressing,
+            start_address: None,
+        };
+
+        let mut builder = RecordBuilder::continuing(&options, region)?;
+
+        for (start, len) in runs {
+            let offset = (start - address) as usize;
+            builder.push_block(start, &data[offset..offset + len])?;
+        }
+
+        new_records.extend(builder.into_records());
+        records.splice(insert_at..insert_at, new_records);
+
+        Ok(IHexFile::from_records(records))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+    use crate::record::file::IHexWriteOptions;
+
+    fn read(text: &str) -> IHexFile {
+        IHexFile::read(text.as_bytes().lines()).unwrap()
+    }
+
+    fn write(file: &IHexFile) -> String {
+        let mut out = Vec::new();
+        file.write(&mut out, &IHexWriteOptions::default()).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn patch_mapped_bytes() {
+        let file = read(":0400000001020304F2\n:00000001FF\n");
+        let patched = file.with_bytes(1, &[0xAA, 0xBB], 16).unwrap();
+
+        assert_eq!(patched.records.len(), file.records.len());
+        assert_eq!(patched.data_bytes().read(0, 4, 0), [0x01, 0xAA, 0xBB, 0x04]);
+
+        // The checksum of the changed record is recalculated
+        assert_eq!(write(&patched), ":0400000001AABB0492\n:00000001FF\n");
+    }
+
+    #[test]
+    fn patch_overlapping_records() {
+        // Only the record that provides the byte in the image is changed
+        let file = read(":0400000001020304F2\n:02000200AABB97\n:00000001FF\n");
+        let patched = file.with_bytes(2, &[0x55], 16).unwrap();
+
+        assert_eq!(
+            write(&patched),
+            ":0400000001020304F2\n:0200020055BBEC\n:00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn append_unmapped_bytes() {
+        let file = read(":0B0010006164647265737320676170A7\n:020000040001F9\n:0400000048656C6C77\n:00000001FF\n");
+        let patched = file.with_bytes(0x1_0004, &[1, 2, 3, 4], 16).unwrap();
+
+        assert_eq!(
+            write(&patched),
+            ":0B0010006164647265737320676170A7\n:020000040001F9\n:0400000048656C6C77\n:0400040001020304EE\n:00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn partially_mapped_bytes() {
+        let file = read(":0400000001020304F2\n:00000001FF\n");
+        let patched = file.with_bytes(2, &[0xAA; 20], 8).unwrap();
+        let text = write(&patched);
+        let memory = read(&text).data_bytes();
+
+        assert_eq!(memory.read(0, 2, 0), [0x01, 0x02]);
+        assert_eq!(memory.read(2, 20, 0), [0xAA; 20]);
+        assert_eq!(memory.len(), 22);
+
+        // New records go before the End of File record
+        assert_eq!(text.matches(":00000001FF").count(), 1);
+        assert!(text.ends_with(":00000001FF\n"));
+    }
+
+    #[test]
+    fn addressing_of_new_records() {
+        // A linear file gets an extended linear address record for another region
+        let file = read(":0400000001020304F2\n:00000001FF\n");
+        let patched = file.with_bytes(0x2_0000, &[0x55], 16).unwrap();
+
+        assert_eq!(patched.filetype(), IHexFileType::IHex32);
+        assert_eq!(
+            read(&write(&patched)).data_bytes().get(0x2_0000),
+            Some(0x55)
+        );
+
+        // A segment file keeps using segment addressing
+        let file = read(":020000021000EC\n:0400000048656C6C77\n:00000001FF\n");
+        let patched = file.with_bytes(0x1_8000, &[0x55], 16).unwrap();
+
+        assert_eq!(patched.filetype(), IHexFileType::IHex16);
+        assert_eq!(
+            read(&write(&patched)).data_bytes().get(0x1_8000),
+            Some(0x55)
+        );
+    }
+
+    #[test]
+    fn address_out_of_range() {
+        let file = read(":00000001FF\n");
+
+        assert!(file.with_bytes(0xFFFF_FFFF, &[1], 16).is_ok());
+        assert!(matches!(
+            file.with_bytes(0xFFFF_FFFF, &[1, 2], 16),
+            Err(BinaryImportError::AddressOutOfRange)
+        ));
+    }
+}
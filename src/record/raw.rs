@@ -3,9 +3,18 @@ use std::{
     fmt::{self, Display, Formatter},
 };
 
-use crate::to_u16_be;
+use crate::utils::to_u16_be;
 
-#[derive(Debug)]
+use super::IHexRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawIHexRecord {
     pub reclen: u8,
     pub load_offset: u16,
@@ -21,6 +30,26 @@ fn add_u16(a: u8, b: u16) -> u8 {
 }
 
 impl RawIHexRecord {
+    /// Creates a new record with the given fields, calculating the
+    /// record length and checksum from the data.
+    ///
+    /// Fails if the data is longer than 255 bytes.
+    pub fn new(load_offset: u16, rectyp: u8, data: Vec<u8>) -> Result<Self, RecordTooLongError> {
+        let reclen =
+            u8::try_from(data.len()).map_err(|_| RecordTooLongError { length: data.len() })?;
+
+        let mut record = RawIHexRecord {
+            reclen,
+            load_offset,
+            rectyp,
+            data,
+            checksum: 0,
+        };
+
+        record.checksum = record.generate_checksum();
+        Ok(record)
+    }
+
     pub fn generate_checksum(&self) -> u8 {
         let non_data_sum = add_u16(self.reclen.wrapping_add(self.rectyp), self.load_offset);
         let data_sum = self
@@ -33,6 +62,73 @@ impl RawIHexRecord {
     pub fn checksum_valid(&self) -> bool {
         self.generate_checksum() == self.checksum
     }
+
+    /// Formats the record as a single `:LLAAAATT[DD...]CC` line, without line ending.
+    pub fn to_ihex_string(&self, case: HexCase) -> String {
+        let mut bytes = Vec::with_capacity(self.data.len() + 5);
+        bytes.push(self.reclen);
+        bytes.extend_from_slice(&self.load_offset.to_be_bytes());
+        bytes.push(self.rectyp);
+        bytes.extend_from_slice(&self.data);
+        bytes.push(self.checksum);
+
+        let encoded = match case {
+            HexCase::Upper => hex::encode_upper(bytes),
+            HexCase::Lower => hex::encode(bytes),
+        };
+
+        format!(":{}", encoded)
+    }
+}
+
+impl Display for RawIHexRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ihex_string(HexCase::Upper))
+    }
+}
+
+/// The data of a record does not fit in the 8-bit record length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordTooLongError {
+    pub length: usize,
+}
+
+impl Display for RecordTooLongError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Record data of {} bytes exceeds the maximum of 255 bytes",
+            self.length
+        )
+    }
+}
+
+impl Error for RecordTooLongError {}
+
+impl TryFrom<&IHexRecord> for RawIHexRecord {
+    type Error = RecordTooLongError;
+
+    fn try_from(record: &IHexRecord) -> Result<Self, Self::Error> {
+        match record {
+            IHexRecord::Data(data) => RawIHexRecord::new(data.naive_address, 0, data.data.clone()),
+            IHexRecord::EndOfFile(eof) => RawIHexRecord::new(0, 1, eof.data.clone()),
+            IHexRecord::ExtendedSegmentAddress(esa) => {
+                let segment = (esa.segment_base >> 4) as u16;
+                RawIHexRecord::new(0, 2, segment.to_be_bytes().to_vec())
+            }
+            IHexRecord::StartSegmentAddress(ssa) => {
+                let mut data = ssa.code_segment.to_be_bytes().to_vec();
+                data.extend_from_slice(&ssa.instruction_pointer.to_be_bytes());
+                RawIHexRecord::new(0, 3, data)
+            }
+            IHexRecord::ExtendedLinearAddress(ela) => {
+                RawIHexRecord::new(0, 4, ela.address_base.to_be_bytes().to_vec())
+            }
+            IHexRecord::StartLinearAddress(sla) => {
+                RawIHexRecord::new(0, 5, sla.entry_point.to_be_bytes().to_vec())
+            }
+        }
+    }
 }
 
 impl From<hex::FromHexError> for IHexParseError {
@@ -0,0 +1,519 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{
+    file::{IHexFile, IHexFileType},
+    IHexRecord,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssueKind {
+    /// The file does not contain an End of File record.
+    MissingEndOfFile,
+    /// The file contains more than one End of File record.
+    MultipleEndOfFile,
+    /// A record other than End of File follows the End of File record.
+    RecordAfterEndOfFile,
+    /// The End of File record has a non-zero length.
+    EndOfFileWithData { length: u8 },
+    /// The data record overlaps with an earlier data record.
+    OverlappingData {
+        other_record: usize,
+        address: u32,
+        length: u64,
+        /// Whether the overlapping bytes differ between the records.
+        conflicting: bool,
+    },
+    /// The data record extends past the end of its 64 KiB segment.
+    DataCrossesSegmentBoundary,
+    /// The file contains both segment and linear addressing records.
+    MixedAddressing,
+    /// The file contains more than one start address record.
+    MultipleStartAddresses,
+    /// The record type is not allowed in the expected file type.
+    RecordNotAllowed { filetype: IHexFileType },
+}
+
+impl ValidationIssueKind {
+    pub const fn severity(&self) -> Severity {
+        match self {
+            ValidationIssueKind::MissingEndOfFile => Severity::Error,
+            ValidationIssueKind::MultipleEndOfFile => Severity::Error,
+            ValidationIssueKind::RecordAfterEndOfFile => Severity::Error,
+            ValidationIssueKind::EndOfFileWithData { .. } => Severity::Warning,
+            ValidationIssueKind::OverlappingData { conflicting, .. } => {
+                if *conflicting {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                }
+            }
+            ValidationIssueKind::DataCrossesSegmentBoundary => Severity::Warning,
+            ValidationIssueKind::MixedAddressing => Severity::Warning,
+            ValidationIssueKind::MultipleStartAddresses => Severity::Warning,
+            ValidationIssueKind::RecordNotAllowed { .. } => Severity::Error,
+        }
+    }
+}
+
+impl Display for ValidationIssueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssueKind::MissingEndOfFile => write!(f, "Missing End of File record"),
+            ValidationIssueKind::MultipleEndOfFile => write!(f, "Multiple End of File records"),
+            ValidationIssueKind::RecordAfterEndOfFile => {
+                write!(f, "Record found after the End of File record")
+            }
+            ValidationIssueKind::EndOfFileWithData { length } => {
+                write!(f, "End of File record has a non-zero length ({})", length)
+            }
+            ValidationIssueKind::OverlappingData {
+                other_record,
+                address,
+                length,
+                conflicting,
+            } => write!(
+                f,
+                "Data at 0x{:x} ({} bytes) overlaps with record #{:06}{}",
+                address,
+                length,
+                other_record,
+                if *conflicting {
+                    " with conflicting bytes"
+                } else {
+                    ""
+                }
+            ),
+            ValidationIssueKind::DataCrossesSegmentBoundary => {
+                write!(f, "Data record crosses a 64 KiB segment boundary")
+            }
+            ValidationIssueKind::MixedAddressing => write!(
+                f,
+                "File contains both segment and linear addressing records"
+            ),
+            ValidationIssueKind::MultipleStartAddresses => {
+                write!(f, "Multiple start address records")
+            }
+            ValidationIssueKind::RecordNotAllowed { filetype } => {
+                write!(f, "Record type is not allowed in a {} file", filetype)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The index of the offending record, if the issue relates to a single record.
+    pub record_index: Option<usize>,
+    pub kind: ValidationIssueKind,
+}
+
+impl ValidationIssue {
+    pub const fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.record_index {
+            Some(i) => write!(f, "{}: record #{:06}: {}", self.severity(), i, self.kind),
+            None => write!(f, "{}: {}", self.severity(), self.kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationOptions {
+    /// If set, records that do not belong in this file type are reported.
+    pub expected_filetype: Option<IHexFileType>,
+}
+
+const fn record_allowed(record: &IHexRecord, filetype: IHexFileType) -> bool {
+    match record {
+        IHexRecord::Data(_) | IHexRecord::EndOfFile(_) => true,
+        IHexRecord::ExtendedSegmentAddress(_) | IHexRecord::StartSegmentAddress(_) => {
+            matches!(filetype, IHexFileType::IHex16)
+        }
+        IHexRecord::ExtendedLinearAddress(_) | IHexRecord::StartLinearAddress(_) => {
+            matches!(filetype, IHexFileType::IHex32)
+        }
+    }
+}
+
+fn check_end_of_file(file: &IHexFile, issues: &mut Vec<ValidationIssue>) {
+    let mut seen_eof = false;
+
+    for (i, record) in file.records.iter().enumerate() {
+        match (record, seen_eof) {
+            (IHexRecord::EndOfFile(_), false) => seen_eof = true,
+            (IHexRecord::EndOfFile(_), true) => issues.push(ValidationIssue {
+                record_index: Some(i),
+                kind: ValidationIssueKind::MultipleEndOfFile,
+            }),
+            (_, true) => issues.push(ValidationIssue {
+                record_index: Some(i),
+                kind: ValidationIssueKind::RecordAfterEndOfFile,
+            }),
+            (_, false) => {}
+        }
+
+        if let IHexRecord::EndOfFile(eof) = record {
+            if !eof.data.is_empty() {
+                issues.push(ValidationIssue {
+                    record_index: Some(i),
+                    kind: ValidationIssueKind::EndOfFileWithData {
+                        length: eof.data.len() as u8,
+                    },
+                });
+            }
+        }
+    }
+
+    if !seen_eof {
+        issues.push(ValidationIssue {
+            record_index: None,
+            kind: ValidationIssueKind::MissingEndOfFile,
+        });
+    }
+}
+
+fn check_addressing(
+    file: &IHexFile,
+    options: &ValidationOptions,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let mut has_segment = false;
+    let mut has_linear = false;
+    let mut seen_start = false;
+
+    for (i, record) in file.records.iter().enumerate() {
+        match record {
+            IHexRecord::ExtendedSegmentAddress(_) => has_segment = true,
+            IHexRecord::ExtendedLinearAddress(_) => has_linear = true,
+            IHexRecord::StartSegmentAddress(_) | IHexRecord::StartLinearAddress(_) => {
+                if seen_start {
+                    issues.push(ValidationIssue {
+                        record_index: Some(i),
+                        kind: ValidationIssueKind::MultipleStartAddresses,
+                    });
+                }
+
+                seen_start = true;
+            }
+            IHexRecord::Data(data) => {
+                if data.naive_address as usize + data.data.len() > 0x10000 {
+                    issues.push(ValidationIssue {
+                        record_index: Some(i),
+                        kind: ValidationIssueKind::DataCrossesSegmentBoundary,
+                    });
+                }
+            }
+            IHexRecord::EndOfFile(_) => {}
+        }
+
+        if let Some(filetype) = options.expected_filetype {
+            if !record_allowed(record, filetype) {
+                issues.push(ValidationIssue {
+                    record_index: Some(i),
+                    kind: ValidationIssueKind::RecordNotAllowed { filetype },
+                });
+            }
+        }
+    }
+
+    if has_segment && has_linear && options.expected_filetype.is_none() {
+        issues.push(ValidationIssue {
+            record_index: None,
+            kind: ValidationIssueKind::MixedAddressing,
+        });
+    }
+}
+
+/// A data record, as a range in the effective address space.
+struct DataSpan<'a> {
+    record_index: usize,
+    start: u64,
+    data: &'a [u8],
+}
+
+impl DataSpan<'_> {
+    fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+}
+
+fn check_overlaps(file: &IHexFile, issues: &mut Vec<ValidationIssue>) {
+    let mut spans: Vec<_> = file
+        .records
+        .iter()
+        .enumerate()
+        .filter_map(|(i, record)| match record {
+            IHexRecord::Data(data) if !data.data.is_empty() => Some(DataSpan {
+                record_index: i,
+                start: data.calc_effective_address() as u64,
+                data: &data.data,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    spans.sort_by_key(|span| (span.start, span.record_index));
+
+    // The earlier spans that reach past the start of the current one
+    let mut open: Vec<&DataSpan> = Vec::new();
+
+    for span in spans.iter() {
+        open.retain(|prev| prev.end() > span.start);
+
+        for prev in open.iter() {
+            let overlap_end = span.end().min(prev.end());
+            let this_bytes = &span.data[..(overlap_end - span.start) as usize];
+            let prev_offset = (span.start - prev.start) as usize;
+            let prev_bytes = &prev.data[prev_offset..prev_offset + this_bytes.len()];
+
+            let (earlier, later) = if prev.record_index < span.record_index {
+                (prev.record_index, span.record_index)
+            } else {
+                (span.record_index, prev.record_index)
+            };
+
+            issues.push(ValidationIssue {
+                record_index: Some(later),
+                kind: ValidationIssueKind::OverlappingData {
+                    other_record: earlier,
+                    address: span.start as u32,
+                    length: overlap_end - span.start,
+                    conflicting: this_bytes != prev_bytes,
+                },
+            });
+        }
+
+        open.push(span);
+    }
+}
+
+impl IHexFile {
+    /// Checks the structure of the file, returning all issues found.
+    pub fn validate(&self, options: &ValidationOptions) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        check_end_of_file(self, &mut issues);
+        check_addressing(self, options, &mut issues);
+        check_overlaps(self, &mut issues);
+
+        issues.sort_by_key(|issue| issue.record_index);
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+    use crate::record::DataRecord;
+
+    fn read(text: &str) -> IHexFile {
+        IHexFile::read(text.as_bytes().lines()).unwrap()
+    }
+
+    fn issues(
+        file: &IHexFile,
+        options: &ValidationOptions,
+    ) -> Vec<(Option<usize>, ValidationIssueKind)> {
+        file.validate(options)
+            .into_iter()
+            .map(|issue| (issue.record_index, issue.kind))
+            .collect()
+    }
+
+    fn data(address: u16, data: Vec<u8>) -> IHexRecord {
+        IHexRecord::Data(DataRecord::new(address, data))
+    }
+
+    fn overlaps(records: Vec<IHexRecord>) -> Vec<(usize, usize, u32, u64, bool)> {
+        let mut issues = Vec::new();
+        check_overlaps(&IHexFile::from_records(records), &mut issues);
+
+        issues
+            .into_iter()
+            .map(|issue| match issue.kind {
+                ValidationIssueKind::OverlappingData {
+                    other_record,
+                    address,
+                    length,
+                    conflicting,
+                } => (
+                    issue.record_index.unwrap(),
+                    other_record,
+                    address,
+                    length,
+                    conflicting,
+                ),
+                kind => panic!("Unexpected issue {:?}", kind),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn end_of_file_checks() {
+        let file = read(":0100000001FE\n:00000001FF\n:0100010002FC\n:00000001FF\n");
+
+        assert_eq!(
+            issues(&file, &ValidationOptions::default()),
+            [
+                (Some(2), ValidationIssueKind::RecordAfterEndOfFile),
+                (Some(3), ValidationIssueKind::MultipleEndOfFile),
+            ]
+        );
+
+        let file = read(":0100000001FE\n");
+
+        assert_eq!(
+            issues(&file, &ValidationOptions::default()),
+            [(None, ValidationIssueKind::MissingEndOfFile)]
+        );
+    }
+
+    #[test]
+    fn end_of_file_with_data() {
+        let file = read(":0100000001FE\n:01000001AA54\n");
+        let issues = file.validate(&ValidationOptions::default());
+
+        assert_eq!(
+            issues,
+            [ValidationIssue {
+                record_index: Some(1),
+                kind: ValidationIssueKind::EndOfFileWithData { length: 1 },
+            }]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn addressing_checks() {
+        let file = read(
+            ":020000021000EC
+:020000040001F9
+:02FFFF000102FD
+:0400000500001234B1
+:0400000310000010D9
+:00000001FF
+",
+        );
+
+        assert_eq!(
+            issues(&file, &ValidationOptions::default()),
+            [
+                (None, ValidationIssueKind::MixedAddressing),
+                (Some(2), ValidationIssueKind::DataCrossesSegmentBoundary),
+                (Some(4), ValidationIssueKind::MultipleStartAddresses),
+            ]
+        );
+
+        // With an expected file type, the records of the other addressing mode are reported
+        let not_allowed = ValidationIssueKind::RecordNotAllowed {
+            filetype: IHexFileType::IHex16,
+        };
+        let options = ValidationOptions {
+            expected_filetype: Some(IHexFileType::IHex16),
+        };
+
+        assert_eq!(
+            issues(&file, &options),
+            [
+                (Some(1), not_allowed.clone()),
+                (Some(2), ValidationIssueKind::DataCrossesSegmentBoundary),
+                (Some(3), not_allowed),
+                (Some(4), ValidationIssueKind::MultipleStartAddresses),
+            ]
+        );
+
+        let options = ValidationOptions {
+            expected_filetype: Some(IHexFileType::IHex8),
+        };
+
+        assert!(issues(&read(":0100000001FE\n:00000001FF\n"), &options).is_empty());
+    }
+
+    #[test]
+    fn no_overlaps() {
+        let records = vec![
+            data(0, vec![0; 16]),
+            data(16, vec![0; 16]),
+            data(0x100, vec![0; 4]),
+            data(0x200, vec![]),
+            data(0x200, vec![]),
+        ];
+
+        assert!(overlaps(records).is_empty());
+    }
+
+    #[test]
+    fn overlap_is_reported_on_the_later_record() {
+        // The record placed at the lower address comes last in the file
+        let records = vec![data(8, vec![1, 2, 3, 4]), data(6, vec![9, 9, 1, 2])];
+
+        assert_eq!(overlaps(records), [(1, 0, 8, 2, false)]);
+
+        let records = vec![data(0, vec![1, 2, 3, 4]), data(2, vec![3, 5, 6])];
+
+        assert_eq!(overlaps(records), [(1, 0, 2, 2, true)]);
+    }
+
+    #[test]
+    fn overlap_with_every_open_span() {
+        let records = vec![
+            data(0, vec![0; 100]),
+            data(10, vec![0; 10]),
+            data(15, vec![1; 15]),
+        ];
+
+        assert_eq!(
+            overlaps(records),
+            [
+                (1, 0, 10, 10, false),
+                (2, 0, 15, 15, true),
+                (2, 1, 15, 5, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn end_of_file_data_is_kept() {
+        let mut file = read(":0100000001FE\n:01000001AA54\n");
+        let expected = ValidationIssue {
+            record_index: Some(2),
+            kind: ValidationIssueKind::EndOfFileWithData { length: 1 },
+        };
+
+        // The issue follows the record when records are inserted before it
+        file.records.insert(0, data(0x10, vec![2]));
+        file.refresh();
+
+        assert_eq!(file.validate(&ValidationOptions::default()), [expected]);
+
+        // The data is written back as it was read
+        let mut written = Vec::new();
+        file.write(&mut written, &Default::default()).unwrap();
+        assert!(String::from_utf8(written)
+            .unwrap()
+            .ends_with(":01000001AA54\n"));
+    }
+}
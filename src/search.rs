@@ -0,0 +1,267 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::memory::MemoryMap;
+
+/// The encoding used to turn a search string into bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEncoding {
+    #[default]
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A sequence of bytes to search for, where `None` matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl SearchPattern {
+    /// Parses a pattern of hex bytes, where `??` matches any byte.
+    /// Bytes may be separated by whitespace or commas, e.g. `DE AD ?? EF` or `DEAD??EF`.
+    pub fn from_hex(text: &str) -> Result<Self, PatternError> {
+        let mut bytes = Vec::new();
+
+        let tokens = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty());
+
+        for token in tokens {
+            let digits = token
+                .strip_prefix("0x")
+                .or(token.strip_prefix("0X"))
+                .unwrap_or(token);
+
+            if digits.len() % 2 != 0 {
+                return Err(PatternError::InvalidHex(token.to_string()));
+            }
+
+            for pair in digits.as_bytes().chunks(2) {
+                let byte = match pair {
+                    b"??" => None,
+                    _ => {
+                        let pair = std::str::from_utf8(pair)
+                            .map_err(|_| PatternError::InvalidHex(token.to_string()))?;
+
+                        Some(
+                            u8::from_str_radix(pair, 16)
+                                .map_err(|_| PatternError::InvalidHex(token.to_string()))?,
+                        )
+                    }
+                };
+
+                bytes.push(byte);
+            }
+        }
+
+        Self::from_pattern(bytes)
+    }
+
+    /// Creates a pattern that matches `text` in the given encoding.
+    pub fn from_text(text: &str, encoding: TextEncoding) -> Result<Self, PatternError> {
+        let bytes: Vec<u8> = match encoding {
+            TextEncoding::Ascii => {
+                if !text.is_ascii() {
+                    return Err(PatternError::NotAscii);
+                }
+
+                text.as_bytes().to_vec()
+            }
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            TextEncoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        };
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Creates a pattern that matches exactly `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PatternError> {
+        Self::from_pattern(bytes.iter().copied().map(Some).collect())
+    }
+
+    fn from_pattern(bytes: Vec<Option<u8>>) -> Result<Self, PatternError> {
+        if bytes.is_empty() {
+            return Err(PatternError::Empty);
+        }
+
+        Ok(SearchPattern { bytes })
+    }
+
+    /// The amount of bytes a match spans.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether the pattern has no bytes, which is never the case for a constructed pattern.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        self.bytes
+            .iter()
+            .zip(data)
+            .all(|(pattern, byte)| pattern.map_or(true, |pattern| pattern == *byte))
+    }
+
+    /// The addresses of all matches in `memory`, in ascending order.
+    ///
+    /// Matches may span multiple records, but never an unmapped address. Matches
+    /// may overlap each other.
+    pub fn find_iter<'a>(&'a self, memory: &'a MemoryMap) -> impl Iterator<Item = u32> + 'a {
+        memory.segments().iter().flat_map(move |seg| {
+            seg.data
+                .windows(self.len())
+                .enumerate()
+                .filter(|(_, window)| self.matches(window))
+                .map(move |(offset, _)| seg.start + offset as u32)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    Empty,
+    InvalidHex(String),
+    NotAscii,
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "The search pattern is empty"),
+            PatternError::InvalidHex(token) => {
+                write!(f, "Invalid hex byte(s) in search pattern: \"{}\"", token)
+            }
+            PatternError::NotAscii => write!(f, "The search string contains non-ASCII characters"),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(segments: &[(u32, &[u8])]) -> MemoryMap {
+        let mut memory = MemoryMap::new();
+
+        for (start, data) in segments {
+            memory.insert(*start, data);
+        }
+
+        memory
+    }
+
+    fn find(pattern: &str, memory: &MemoryMap) -> Vec<u32> {
+        SearchPattern::from_hex(pattern)
+            .unwrap()
+            .find_iter(memory)
+            .collect()
+    }
+
+    #[test]
+    fn hex_pattern_syntax() {
+        let expected = SearchPattern {
+            bytes: vec![Some(0xDE), Some(0xAD), None, Some(0xEF)],
+        };
+
+        for text in [
+            "DE AD ?? EF",
+            "DEAD??EF",
+            "0xde, 0xad,??,0XEF",
+            " de\tad ?? ef ",
+        ] {
+            assert_eq!(
+                SearchPattern::from_hex(text),
+                Ok(expected.clone()),
+                "{}",
+                text
+            );
+        }
+
+        for token in ["DEA", "G0", "?", "?A", "é0", "éé"] {
+            assert_eq!(
+                SearchPattern::from_hex(token),
+                Err(PatternError::InvalidHex(token.to_string()))
+            );
+        }
+
+        assert_eq!(SearchPattern::from_hex(""), Err(PatternError::Empty));
+        assert_eq!(SearchPattern::from_hex(" , "), Err(PatternError::Empty));
+    }
+
+    #[test]
+    fn wildcard_matches() {
+        let memory = memory(&[(
+            0x100,
+            &[
+                0x12, 0xDE, 0xAD, 0x00, 0xEF, 0xDE, 0xAD, 0xFF, 0xEF, 0xDE, 0xAD,
+            ],
+        )]);
+
+        assert_eq!(find("DE AD ?? EF", &memory), [0x101, 0x105]);
+        assert_eq!(find("?? ?? ?? ??", &memory).len(), 8);
+        assert_eq!(find("AD ?? ?? ?? ??", &memory), [0x102, 0x106]);
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        let memory = memory(&[(0, &[0xAA; 5]), (0x10, &[1, 2, 1, 2, 1])]);
+
+        assert_eq!(find("AA AA", &memory), [0, 1, 2, 3]);
+        assert_eq!(find("01 ?? 01", &memory), [0x10, 0x12]);
+    }
+
+    #[test]
+    fn matches_stop_at_unmapped_addresses() {
+        // The segments touch, so they are merged and the pattern spans both inserts
+        let touching = memory(&[(0x10, &[0x01, 0x02]), (0x12, &[0x03])]);
+        assert_eq!(find("02 03", &touching), [0x11]);
+
+        let gap = memory(&[(0x10, &[0x01, 0x02]), (0x13, &[0x03])]);
+        assert!(find("02 03", &gap).is_empty());
+        assert!(find("02 ?? 03", &gap).is_empty());
+
+        // A pattern longer than a segment never matches it
+        assert!(find("01 02 03 04", &touching).is_empty());
+        assert!(find("01", &MemoryMap::new()).is_empty());
+    }
+
+    #[test]
+    fn text_patterns() {
+        let bytes = |bytes: &[u8]| SearchPattern::from_bytes(bytes);
+
+        assert_eq!(
+            SearchPattern::from_text("Hi", TextEncoding::Ascii),
+            bytes(b"Hi")
+        );
+        assert_eq!(
+            SearchPattern::from_text("Hié", TextEncoding::Utf8),
+            bytes(&[b'H', b'i', 0xC3, 0xA9])
+        );
+        assert_eq!(
+            SearchPattern::from_text("Hi", TextEncoding::Utf16Le),
+            bytes(&[b'H', 0, b'i', 0])
+        );
+        assert_eq!(
+            SearchPattern::from_text("Hi", TextEncoding::Utf16Be),
+            bytes(&[0, b'H', 0, b'i'])
+        );
+        assert_eq!(
+            SearchPattern::from_text("Hié", TextEncoding::Ascii),
+            Err(PatternError::NotAscii)
+        );
+        assert_eq!(
+            SearchPattern::from_text("", TextEncoding::Utf8),
+            Err(PatternError::Empty)
+        );
+    }
+}
@@ -1,4 +1,3 @@
-#[macro_export]
 macro_rules! to_u16_be {
     ($x:expr) => {{
         if $x.len() != 2 {
@@ -9,7 +8,6 @@ macro_rules! to_u16_be {
     }};
 }
 
-#[macro_export]
 macro_rules! to_u32_be {
     ($x:expr) => {{
         if $x.len() != 4 {
@@ -19,3 +17,6 @@ macro_rules! to_u32_be {
         u32::from_be_bytes([$x[0], $x[1], $x[2], $x[3]])
     }};
 }
+
+pub(crate) use to_u16_be;
+pub(crate) use to_u32_be;